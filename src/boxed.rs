@@ -1,14 +1,20 @@
 //! Safe RAII wrappers for `WebPFree`.
 
+use libwebp_sys as sys;
+use std::alloc::{handle_alloc_error, Layout};
+use std::borrow::{Borrow, BorrowMut};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::*;
 use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::ptr::NonNull;
+use std::ptr::{self, NonNull};
 use std::slice;
 
+use crate::encode::WebPPicture;
 use crate::error::WebPSimpleError;
 
 /// A safe RAII wrapper for `WebPFree`.
@@ -88,24 +94,262 @@ impl<T: ?Sized> Drop for WebpBox<T> {
     }
 }
 
-#[cfg(feature = "0.5")]
-use libwebp_sys::WebPFree;
+#[cfg(feature = "0_5")]
+pub(crate) use libwebp_sys::WebPFree;
 
-#[cfg(not(feature = "0.5"))]
+#[cfg(not(feature = "0_5"))]
 #[allow(non_snake_case)]
-unsafe fn WebPFree(ptr: *mut c_void) {
+pub(crate) unsafe fn WebPFree(ptr: *mut c_void) {
     extern "C" {
         fn free(ptr: *mut c_void);
     }
     free(ptr);
 }
 
+#[cfg(feature = "1_1")]
+use libwebp_sys::WebPMalloc;
+
+// libwebp older than 1.1.0 always frees with the system allocator.
+#[cfg(not(feature = "1_1"))]
+#[allow(non_snake_case)]
+unsafe fn WebPMalloc(size: usize) -> *mut c_void {
+    extern "C" {
+        fn malloc(size: usize) -> *mut c_void;
+    }
+    malloc(size)
+}
+
+impl WebpBox<[u8]> {
+    /// Allocates a zero-filled buffer of `len` bytes via `WebPMalloc`.
+    ///
+    /// The buffer can be handed over to libwebp, which frees it via
+    /// `WebPFree`.
+    ///
+    /// ## Panics
+    ///
+    /// Aborts on allocation failure.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::boxed::WebpBox;
+    ///
+    /// let buf = WebpBox::<[u8]>::new_zeroed(16);
+    /// assert_eq!(&buf[..], &[0; 16]);
+    /// ```
+    pub fn new_zeroed(len: usize) -> WebpBox<[u8]> {
+        unsafe {
+            let ptr = alloc_bytes(len);
+            ptr::write_bytes(ptr, 0, len);
+            WebpBox::from_raw(slice::from_raw_parts_mut(ptr, len))
+        }
+    }
+
+    /// Allocates a buffer via `WebPMalloc` and copies `data` into it.
+    ///
+    /// ## Panics
+    ///
+    /// Aborts on allocation failure.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::boxed::WebpBox;
+    ///
+    /// let buf = WebpBox::from_slice(b"RIFF");
+    /// assert_eq!(&buf[..], b"RIFF");
+    /// ```
+    pub fn from_slice(data: &[u8]) -> WebpBox<[u8]> {
+        unsafe {
+            let ptr = alloc_bytes(data.len());
+            ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+            WebpBox::from_raw(slice::from_raw_parts_mut(ptr, data.len()))
+        }
+    }
+}
+
+// Allocates at least one byte so that a null pointer always means failure.
+pub(crate) unsafe fn alloc_bytes(len: usize) -> *mut u8 {
+    let ptr = WebPMalloc(len.max(1)) as *mut u8;
+    if ptr.is_null() {
+        handle_alloc_error(Layout::from_size_align_unchecked(len.max(1), 1));
+    }
+    ptr
+}
+
 impl<T: fmt::Debug + ?Sized> fmt::Debug for WebpBox<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self as &T, f)
     }
 }
 
+impl<T: ?Sized> AsRef<T> for WebpBox<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsMut<T> for WebpBox<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for WebpBox<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> BorrowMut<T> for WebpBox<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: PartialEq + ?Sized> PartialEq for WebpBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        PartialEq::eq(&**self, &**other)
+    }
+}
+
+impl<T: Eq + ?Sized> Eq for WebpBox<T> {}
+
+impl<T: Hash + ?Sized> Hash for WebpBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+/// Copies the contents into a new allocation via `WebPMalloc`.
+impl Clone for WebpBox<[u8]> {
+    fn clone(&self) -> Self {
+        WebpBox::from_slice(self)
+    }
+}
+
+impl WebpBox<[u8]> {
+    /// Copies the contents into a `Vec<u8>`, freeing the original buffer.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    /// Returns a cursor reading from the buffer.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    /// use libwebp::boxed::WebpBox;
+    ///
+    /// let buf = WebpBox::from_slice(b"RIFF");
+    /// let mut tag = [0; 4];
+    /// buf.reader().read_exact(&mut tag).unwrap();
+    /// assert_eq!(&tag, b"RIFF");
+    /// ```
+    pub fn reader(&self) -> io::Cursor<&[u8]> {
+        io::Cursor::new(self)
+    }
+
+    /// Returns a cursor overwriting the buffer in place.
+    ///
+    /// The buffer doesn't grow; writes past the end are truncated.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use std::io::Write;
+    /// use libwebp::boxed::WebpBox;
+    ///
+    /// let mut buf = WebpBox::<[u8]>::new_zeroed(4);
+    /// buf.writer().write_all(b"WEBP").unwrap();
+    /// assert_eq!(&buf[..], b"WEBP");
+    /// ```
+    pub fn writer(&mut self) -> io::Cursor<&mut [u8]> {
+        io::Cursor::new(self)
+    }
+}
+
+impl<'a> IntoIterator for &'a WebpBox<[u8]> {
+    type Item = &'a u8;
+    type IntoIter = slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut WebpBox<[u8]> {
+    type Item = &'a mut u8;
+    type IntoIter = slice::IterMut<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for WebpBox<[u8]> {
+    type Item = u8;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let end = self.len();
+        IntoIter {
+            buf: self,
+            start: 0,
+            end,
+        }
+    }
+}
+
+/// An owning iterator over the bytes of `WebpBox<[u8]>`.
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    buf: WebpBox<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(self.buf[self.start - 1])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<u8> {
+        if self.start < self.end {
+            self.end -= 1;
+            Some(self.buf[self.end])
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+/// Converts into `Bytes` without copying; the buffer is freed via
+/// `WebPFree` when the last `Bytes` referencing it is dropped.
+#[cfg(feature = "bytes")]
+impl From<WebpBox<[u8]>> for bytes::Bytes {
+    fn from(buf: WebpBox<[u8]>) -> Self {
+        bytes::Bytes::from_owner(buf)
+    }
+}
+
 #[inline]
 pub(crate) unsafe fn wrap_bytes<F>(
     ptr: *mut u8,
@@ -235,6 +479,270 @@ impl fmt::Debug for WebpYuvBox {
     }
 }
 
+/// An owned Y'UV(A) image, as returned by [`WebPDecodeYUVA`].
+///
+/// Unlike [`WebpYuvBox`], it carries its own dimensions and strides. The U
+/// and V planes have the dimension of `(width + 1) / 2` and
+/// `(height + 1) / 2`, and share a common stride.
+///
+/// [`WebPDecodeYUVA`]: ../fn.WebPDecodeYUVA.html
+/// [`WebpYuvBox`]: struct.WebpYuvBox.html
+pub struct WebpYuvaImage {
+    buffer: sys::WebPDecBuffer,
+}
+
+unsafe impl Send for WebpYuvaImage {}
+unsafe impl Sync for WebpYuvaImage {}
+
+impl WebpYuvaImage {
+    /// Creates `WebpYuvaImage` from a decoded buffer.
+    ///
+    /// ## Safety
+    ///
+    /// - `buffer` must have been decoded in `MODE_YUV` or `MODE_YUVA`.
+    /// - `buffer` must be freeable via `WebPFreeDecBuffer`.
+    pub unsafe fn from_raw(buffer: sys::WebPDecBuffer) -> WebpYuvaImage {
+        debug_assert!(buffer.colorspace == sys::MODE_YUV || buffer.colorspace == sys::MODE_YUVA);
+        Self { buffer }
+    }
+
+    /// Turns `WebpYuvaImage` into the raw buffer without freeing anything.
+    pub fn into_raw(self) -> sys::WebPDecBuffer {
+        let buffer = self.buffer;
+        mem::forget(self);
+        buffer
+    }
+
+    fn yuva(&self) -> &sys::WebPYUVABuffer {
+        unsafe { &self.buffer.u.YUVA }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.buffer.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.buffer.height as u32
+    }
+
+    /// Width of the U and V planes.
+    pub fn uv_width(&self) -> u32 {
+        (self.width() + 1) / 2
+    }
+
+    /// Height of the U and V planes.
+    pub fn uv_height(&self) -> u32 {
+        (self.height() + 1) / 2
+    }
+
+    /// Returns `true` if the image has the alpha plane.
+    pub fn has_alpha(&self) -> bool {
+        !self.yuva().a.is_null()
+    }
+
+    pub fn y_stride(&self) -> u32 {
+        self.yuva().y_stride as u32
+    }
+
+    /// Common stride of the U and V planes.
+    pub fn uv_stride(&self) -> u32 {
+        self.yuva().u_stride as u32
+    }
+
+    /// Stride of the alpha plane, if any.
+    pub fn a_stride(&self) -> Option<u32> {
+        if self.has_alpha() {
+            Some(self.yuva().a_stride as u32)
+        } else {
+            None
+        }
+    }
+
+    pub fn y(&self) -> &[u8] {
+        let yuva = self.yuva();
+        unsafe { slice::from_raw_parts(yuva.y, yuva.y_size) }
+    }
+
+    pub fn y_mut(&mut self) -> &mut [u8] {
+        let yuva = *self.yuva();
+        unsafe { slice::from_raw_parts_mut(yuva.y, yuva.y_size) }
+    }
+
+    pub fn u(&self) -> &[u8] {
+        let yuva = self.yuva();
+        unsafe { slice::from_raw_parts(yuva.u, yuva.u_size) }
+    }
+
+    pub fn u_mut(&mut self) -> &mut [u8] {
+        let yuva = *self.yuva();
+        unsafe { slice::from_raw_parts_mut(yuva.u, yuva.u_size) }
+    }
+
+    pub fn v(&self) -> &[u8] {
+        let yuva = self.yuva();
+        unsafe { slice::from_raw_parts(yuva.v, yuva.v_size) }
+    }
+
+    pub fn v_mut(&mut self) -> &mut [u8] {
+        let yuva = *self.yuva();
+        unsafe { slice::from_raw_parts_mut(yuva.v, yuva.v_size) }
+    }
+
+    pub fn a(&self) -> Option<&[u8]> {
+        let yuva = self.yuva();
+        if self.has_alpha() {
+            Some(unsafe { slice::from_raw_parts(yuva.a, yuva.a_size) })
+        } else {
+            None
+        }
+    }
+
+    pub fn a_mut(&mut self) -> Option<&mut [u8]> {
+        let yuva = *self.yuva();
+        if self.has_alpha() {
+            Some(unsafe { slice::from_raw_parts_mut(yuva.a, yuva.a_size) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the rows of the Y plane, without the padding.
+    pub fn y_rows(&self) -> Rows<'_> {
+        Rows::new(self.y(), self.width(), self.height(), self.y_stride())
+    }
+
+    /// Iterates over the rows of the U plane, without the padding.
+    pub fn u_rows(&self) -> Rows<'_> {
+        Rows::new(
+            self.u(),
+            self.uv_width(),
+            self.uv_height(),
+            self.uv_stride(),
+        )
+    }
+
+    /// Iterates over the rows of the V plane, without the padding.
+    pub fn v_rows(&self) -> Rows<'_> {
+        Rows::new(
+            self.v(),
+            self.uv_width(),
+            self.uv_height(),
+            self.uv_stride(),
+        )
+    }
+
+    /// Iterates over the rows of the alpha plane, without the padding.
+    pub fn a_rows(&self) -> Option<Rows<'_>> {
+        let a_stride = self.a_stride()?;
+        Some(Rows::new(self.a()?, self.width(), self.height(), a_stride))
+    }
+
+    /// Converts the image to packed RGBA samples with the stride of
+    /// `width * 4`.
+    ///
+    /// The conversion is done by libwebp's `WebPPictureYUVAToARGB` on a copy
+    /// of the planes. Images without alpha become opaque.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if libwebp fails to allocate the ARGB plane.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut picture = WebPPicture::from_yuva_owned(
+            self.width(),
+            self.height(),
+            self.y().to_vec(),
+            self.y_stride(),
+            self.u().to_vec(),
+            self.v().to_vec(),
+            self.uv_stride(),
+            self.a().map(|a| (a.to_vec(), self.a_stride().unwrap())),
+        );
+        picture
+            .yuva_to_argb()
+            .expect("WebPPictureYUVAToARGB failed");
+        let width = self.width() as usize;
+        let mut rgba = Vec::with_capacity(width * self.height() as usize * 4);
+        for row in picture
+            .argb()
+            .unwrap()
+            .chunks(picture.argb_stride() as usize)
+        {
+            for &px in &row[..width] {
+                rgba.extend_from_slice(&[
+                    (px >> 16) as u8,
+                    (px >> 8) as u8,
+                    px as u8,
+                    (px >> 24) as u8,
+                ]);
+            }
+        }
+        rgba
+    }
+}
+
+impl Drop for WebpYuvaImage {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPFreeDecBuffer(&mut self.buffer);
+        }
+    }
+}
+
+impl fmt::Debug for WebpYuvaImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebpYuvaImage")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .field("y_stride", &self.y_stride())
+            .field("uv_stride", &self.uv_stride())
+            .field("a_stride", &self.a_stride())
+            .finish()
+    }
+}
+
+/// An iterator over the rows of a plane, created by [`WebpYuvaImage::y_rows`]
+/// and the like.
+///
+/// [`WebpYuvaImage::y_rows`]: struct.WebpYuvaImage.html#method.y_rows
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    data: &'a [u8],
+    width: usize,
+    stride: usize,
+    remaining: usize,
+}
+
+impl<'a> Rows<'a> {
+    fn new(data: &'a [u8], width: u32, height: u32, stride: u32) -> Self {
+        Self {
+            data,
+            width: width as usize,
+            stride: stride as usize,
+            remaining: height as usize,
+        }
+    }
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let row = &self.data[..self.width];
+        self.data = &self.data[self.stride.min(self.data.len())..];
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Rows<'a> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,5 +765,104 @@ mod tests {
         is_sync::<WebpYuvBox>();
         is_unwind_safe::<WebpYuvBox>();
         is_ref_unwind_safe::<WebpYuvBox>();
+
+        is_send::<WebpYuvaImage>();
+        is_sync::<WebpYuvaImage>();
+    }
+
+    #[test]
+    fn test_new_zeroed() {
+        let mut buf = WebpBox::<[u8]>::new_zeroed(1000);
+        assert_eq!(buf.len(), 1000);
+        assert!(buf.iter().all(|&x| x == 0));
+        buf[999] = 1;
+        assert_eq!(buf[999], 1);
+
+        let buf = WebpBox::<[u8]>::new_zeroed(0);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let buf = WebpBox::from_slice(&[1, 2, 3]);
+        assert_eq!(&buf[..], &[1, 2, 3]);
+        let buf = WebpBox::from_slice(&[]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_traits() {
+        use std::collections::HashSet;
+
+        let buf = WebpBox::from_slice(&[1, 2, 3]);
+        let copy = buf.clone();
+        assert_eq!(buf, copy);
+        assert_ne!(buf.as_ptr(), copy.as_ptr());
+        assert_ne!(buf, WebpBox::from_slice(&[1, 2]));
+        assert_eq!(AsRef::<[u8]>::as_ref(&buf), &[1, 2, 3]);
+
+        let mut set = HashSet::new();
+        set.insert(copy);
+        assert!(set.contains(&[1, 2, 3][..]));
+        assert!(set.contains(&buf));
+
+        assert_eq!(buf.clone().into_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut buf = WebpBox::from_slice(&[1, 2, 3, 4]);
+        for x in &mut buf {
+            *x *= 2;
+        }
+        assert_eq!((&buf).into_iter().sum::<u8>(), 20);
+        let mut iter = buf.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next_back(), Some(8));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![4, 6]);
+    }
+
+    #[test]
+    fn test_io() {
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let mut buf = WebpBox::<[u8]>::new_zeroed(6);
+        let mut writer = buf.writer();
+        writer.write_all(b"abc").unwrap();
+        assert_eq!(writer.write(b"defgh").unwrap(), 3);
+        assert_eq!(&buf[..], b"abcdef");
+
+        let mut reader = buf.reader();
+        reader.seek(SeekFrom::Start(2)).unwrap();
+        let mut s = String::new();
+        reader.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "cdef");
+
+        let mut s = String::new();
+        io::Cursor::new(buf).read_to_string(&mut s).unwrap();
+        assert_eq!(s, "abcdef");
+    }
+
+    #[test]
+    #[cfg(feature = "bytes")]
+    fn test_into_bytes() {
+        let buf = WebpBox::from_slice(b"WEBP");
+        let ptr = buf.as_ptr();
+        let bytes = bytes::Bytes::from(buf);
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(&bytes[..], b"WEBP");
+        let tail = bytes.slice(2..);
+        drop(bytes);
+        assert_eq!(&tail[..], b"BP");
+    }
+
+    #[test]
+    #[cfg(feature = "0_5")]
+    fn test_free_by_libwebp() {
+        let buf = WebpBox::from_slice(&[1, 2, 3]);
+        unsafe {
+            sys::WebPFree(WebpBox::into_raw(buf) as *mut c_void);
+        }
     }
 }
@@ -1,4 +1,5 @@
 use libwebp_sys as sys;
+use std::fmt;
 use std::marker::{PhantomPinned, Unpin};
 use std::mem;
 use std::os::raw::*;
@@ -7,7 +8,7 @@ use std::pin::Pin;
 use std::ptr::{self, NonNull};
 use std::slice;
 
-use crate::boxed::{wrap_bytes, WebpBox, WebpYuvBox};
+use crate::boxed::{wrap_bytes, WebpBox, WebpYuvBox, WebpYuvaImage};
 use crate::error::WebPSimpleError;
 
 /// Return the decoder's version number, packed in hexadecimal using 8bits for
@@ -166,7 +167,7 @@ pub fn WebPDecodeBGR(data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPSimpl
 
 /// Decodes WebP images pointed to by `data` to Y'UV format[^1].
 ///
-/// [^1] Also named Y'CbCr. See: [http://en.wikipedia.org/wiki/YCbCr](http://en.wikipedia.org/wiki/YCbCr)
+/// [^1]: Also named Y'CbCr. See: [http://en.wikipedia.org/wiki/YCbCr](http://en.wikipedia.org/wiki/YCbCr)
 ///
 /// ## Return value
 ///
@@ -180,6 +181,11 @@ pub fn WebPDecodeBGR(data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPSimpl
 ///
 /// [`WebpYuvBox`]: boxed/struct.WebPYuvBox.html
 ///
+/// See [`WebPDecodeYUVA`] for a variant returning a self-describing image,
+/// including the alpha plane.
+///
+/// [`WebPDecodeYUVA`]: fn.WebPDecodeYUVA.html
+///
 /// The dimension of the U and V planes are both `(width + 1) / 2` and `(height + 1)/ 2`.
 /// The Y buffer has a stride returned as `stride`, while U and V
 /// have a common stride returned as `uv_stride`.
@@ -253,6 +259,60 @@ pub fn WebPDecodeYUV(data: &[u8]) -> Result<(u32, u32, u32, u32, WebpYuvBox), We
     }
 }
 
+/// Decodes WebP images pointed to by `data` to Y'UV format, along with the
+/// alpha plane if the bitstream contains transparency.
+///
+/// Unlike [`WebPDecodeYUV`], the returned [`WebpYuvaImage`] knows its own
+/// dimensions and strides.
+///
+/// [`WebPDecodeYUV`]: fn.WebPDecodeYUV.html
+/// [`WebpYuvaImage`]: boxed/struct.WebpYuvaImage.html
+///
+/// ## Errors
+///
+/// Returns `Err` if `data` doesn't contain a valid WebP image.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::WebPDecodeYUVA;
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let image = WebPDecodeYUVA(data).expect("Invalid WebP data");
+/// # assert_eq!((image.width(), image.height()), (128, 128));
+/// assert_eq!(image.y_rows().len(), image.height() as usize);
+/// for row in image.u_rows() {
+///     assert_eq!(row.len(), image.uv_width() as usize);
+/// }
+/// let rgba = image.to_rgba();
+/// assert_eq!(rgba.len(), image.width() as usize * image.height() as usize * 4);
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPDecodeYUVA(data: &[u8]) -> Result<WebpYuvaImage, WebPSimpleError> {
+    let mut config: sys::WebPDecoderConfig = unsafe { mem::zeroed() };
+    if unsafe { sys::WebPInitDecoderConfig(&mut config) } == 0 {
+        panic!("libwebp version mismatch");
+    }
+    let status = unsafe { sys::WebPGetFeatures(data.as_ptr(), data.len(), &mut config.input) };
+    if status != sys::VP8_STATUS_OK {
+        return Err(WebPSimpleError);
+    }
+    config.output.colorspace = if config.input.has_alpha != 0 {
+        sys::MODE_YUVA
+    } else {
+        sys::MODE_YUV
+    };
+    let status = unsafe { sys::WebPDecode(data.as_ptr(), data.len(), &mut config) };
+    if status == sys::VP8_STATUS_OK {
+        Ok(unsafe { WebpYuvaImage::from_raw(config.output) })
+    } else {
+        unsafe { sys::WebPFreeDecBuffer(&mut config.output) };
+        Err(WebPSimpleError)
+    }
+}
+
 /// Decodes WebP images pointed to by `data` and writes RGBA samples to
 /// `output_buffer`.
 ///
@@ -735,6 +795,103 @@ impl VP8StatusCode {
     }
 }
 
+impl fmt::Display for VP8StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::VP8StatusCode::*;
+
+        f.write_str(match *self {
+            VP8_STATUS_OK => "no error",
+            VP8_STATUS_OUT_OF_MEMORY => "out of memory",
+            VP8_STATUS_INVALID_PARAM => "invalid parameter",
+            VP8_STATUS_BITSTREAM_ERROR => "bitstream error",
+            VP8_STATUS_UNSUPPORTED_FEATURE => "unsupported feature",
+            VP8_STATUS_SUSPENDED => "decoding suspended",
+            VP8_STATUS_USER_ABORT => "abort request by user",
+            VP8_STATUS_NOT_ENOUGH_DATA => "not enough data",
+        })
+    }
+}
+
+impl std::error::Error for VP8StatusCode {}
+
+/// Bitstream format, as reported by [`WebPGetFeatures`].
+///
+/// [`WebPGetFeatures`]: fn.WebPGetFeatures.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPBitstreamFormat {
+    /// Undefined, or mixed (e.g. animations with both lossy and lossless
+    /// frames)
+    Undefined = 0,
+    Lossy = 1,
+    Lossless = 2,
+}
+
+impl WebPBitstreamFormat {
+    pub fn from_raw(raw: c_int) -> Self {
+        use self::WebPBitstreamFormat::*;
+
+        match raw {
+            0 => Undefined,
+            1 => Lossy,
+            2 => Lossless,
+            _ => panic!("WebPBitstreamFormat::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> c_int {
+        self as c_int
+    }
+}
+
+/// Features gathered from the bitstream by [`WebPGetFeatures`].
+///
+/// [`WebPGetFeatures`]: fn.WebPGetFeatures.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WebPBitstreamFeatures {
+    pub width: u32,
+    pub height: u32,
+    pub has_alpha: bool,
+    pub has_animation: bool,
+    pub format: WebPBitstreamFormat,
+}
+
+/// Retrieves features from the bitstream, without decoding the image.
+///
+/// ## Errors
+///
+/// Returns `Err(VP8_STATUS_NOT_ENOUGH_DATA)` if `data` is too short to
+/// determine the features, and another status code if `data` is invalid.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPBitstreamFormat, WebPGetFeatures};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let features = WebPGetFeatures(data).unwrap();
+/// assert_eq!((features.width, features.height), (128, 128));
+/// assert!(!features.has_alpha);
+/// assert_eq!(features.format, WebPBitstreamFormat::Lossy);
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPGetFeatures(data: &[u8]) -> Result<WebPBitstreamFeatures, VP8StatusCode> {
+    let mut features: sys::WebPBitstreamFeatures = unsafe { mem::zeroed() };
+    let status = unsafe { sys::WebPGetFeatures(data.as_ptr(), data.len(), &mut features) };
+    if status == sys::VP8_STATUS_OK {
+        Ok(WebPBitstreamFeatures {
+            width: features.width as u32,
+            height: features.height as u32,
+            has_alpha: features.has_alpha != 0,
+            has_animation: features.has_animation != 0,
+            format: WebPBitstreamFormat::from_raw(features.format),
+        })
+    } else {
+        Err(VP8StatusCode::from_raw(status))
+    }
+}
+
 // We need this to convert NonNull<T> -> NonNull<U> where T, U are extern.
 macro_rules! cast_nonnull {
     ($e:expr, $T:ty) => {
@@ -762,10 +919,22 @@ impl std::fmt::Debug for WebPIDecoder {
 }
 
 impl WebPIDecoder {
+    /// Creates a pinned reference from a raw pointer.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must be a valid, live incremental decoder.
+    /// - The decoder must not be mutated during `'a`.
     pub unsafe fn from_ptr<'a>(raw: *const sys::WebPIDecoder) -> Pin<&'a Self> {
         Pin::new_unchecked(&*(raw as *const WebPIDecoder))
     }
 
+    /// Creates a pinned mutable reference from a raw pointer.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must be a valid, live incremental decoder.
+    /// - The decoder must be exclusively accessible during `'a`.
     pub unsafe fn from_mut_ptr<'a>(raw: *mut sys::WebPIDecoder) -> Pin<&'a mut Self> {
         Pin::new_unchecked(&mut *(raw as *mut WebPIDecoder))
     }
@@ -798,6 +967,13 @@ impl Drop for WebPIDecoderBox {
 }
 
 impl WebPIDecoderBox {
+    /// Creates `WebPIDecoderBox` from a raw pointer.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must be a valid incremental decoder.
+    /// - The decoder must be exclusively owned by the caller.
+    /// - `raw` must be freeable via `WebPIDelete`.
     pub unsafe fn from_raw(raw: NonNull<sys::WebPIDecoder>) -> Self {
         WebPIDecoderBox(cast_nonnull!(raw, WebPIDecoder))
     }
@@ -1097,6 +1273,72 @@ mod tests {
         assert_eq!(&buf.v()[..6], &[161, 161, 161, 161, 161, 161]);
     }
 
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDecodeYUVA() {
+        let image = WebPDecodeYUVA(&lena()).unwrap();
+        let (_, _, stride, uv_stride, buf) = WebPDecodeYUV(&lena()).unwrap();
+        assert_eq!((image.width(), image.height()), (128, 128));
+        assert_eq!((image.uv_width(), image.uv_height()), (64, 64));
+        assert_eq!((image.y_stride(), image.uv_stride()), (stride, uv_stride));
+        assert!(!image.has_alpha());
+        assert_eq!(image.a_stride(), None);
+        assert!(image.a_rows().is_none());
+        assert!(image.y_rows().eq(buf.y().chunks(stride as usize)));
+        assert!(image.u_rows().eq(buf.u().chunks(uv_stride as usize)));
+        assert!(image.v_rows().eq(buf.v().chunks(uv_stride as usize)));
+
+        let (_, _, expected) = WebPDecodeRGBA(&lena()).unwrap();
+        assert_eq!(&image.to_rgba()[..], &expected[..]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDecodeYUVA_alpha() {
+        let rgba = (0..3 * 5)
+            .flat_map(|i| vec![128, 128, 128, i as u8 * 16])
+            .collect::<Vec<_>>();
+        let data = crate::WebPEncodeLosslessRGBA(&rgba, 3, 5, 3 * 4).unwrap();
+        let image = WebPDecodeYUVA(&data).unwrap();
+        assert_eq!((image.uv_width(), image.uv_height()), (2, 3));
+        assert!(image.has_alpha());
+        let alpha = image
+            .a_rows()
+            .unwrap()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(alpha, rgba.chunks(4).map(|px| px[3]).collect::<Vec<_>>());
+        let output = image.to_rgba();
+        assert_eq!(output.len(), rgba.len());
+        for (px, expected) in output.chunks(4).zip(rgba.chunks(4)) {
+            assert_eq!(px[3], expected[3]);
+            // RGB under fully transparent pixels is discarded by the encoder.
+            assert!(expected[3] == 0 || px[..3].iter().all(|&x| (i32::from(x) - 128).abs() <= 2));
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPGetFeatures() {
+        let data = lena();
+        let features = WebPGetFeatures(&data).unwrap();
+        assert_eq!((features.width, features.height), (128, 128));
+        assert!(!features.has_alpha);
+        assert!(!features.has_animation);
+        assert_eq!(features.format, WebPBitstreamFormat::Lossy);
+        assert_eq!(
+            WebPGetFeatures(&data[..10]),
+            Err(VP8StatusCode::VP8_STATUS_NOT_ENOUGH_DATA)
+        );
+
+        let rgba = [0, 0, 0, 0, 255, 255, 255, 255];
+        let data = crate::WebPEncodeLosslessRGBA(&rgba, 2, 1, 8).unwrap();
+        let features = WebPGetFeatures(&data).unwrap();
+        assert!(features.has_alpha);
+        assert_eq!(features.format, WebPBitstreamFormat::Lossless);
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn test_WebPDecodeRGBAInto() {
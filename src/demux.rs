@@ -0,0 +1,931 @@
+use libwebp_sys as sys;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::*;
+use std::ptr::NonNull;
+use std::slice;
+
+use crate::error::WebPSimpleError;
+use crate::mux_types::*;
+
+/// A feature queried with [`WebPDemuxGetI`].
+///
+/// [`WebPDemuxGetI`]: fn.WebPDemuxGetI.html
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPFormatFeature {
+    /// The flags of the `VP8X` chunk, see [`WebPFeatureFlags`].
+    ///
+    /// [`WebPFeatureFlags`]: struct.WebPFeatureFlags.html
+    WEBP_FF_FORMAT_FLAGS = 0,
+    WEBP_FF_CANVAS_WIDTH = 1,
+    WEBP_FF_CANVAS_HEIGHT = 2,
+    /// Only meaningful for animations.
+    WEBP_FF_LOOP_COUNT = 3,
+    /// Only meaningful for animations.
+    WEBP_FF_BACKGROUND_COLOR = 4,
+    /// The number of frames, 1 for still images.
+    WEBP_FF_FRAME_COUNT = 5,
+}
+
+impl WebPFormatFeature {
+    pub fn from_raw(raw: sys::WebPFormatFeature) -> Self {
+        use self::WebPFormatFeature::*;
+
+        match raw {
+            sys::WEBP_FF_FORMAT_FLAGS => WEBP_FF_FORMAT_FLAGS,
+            sys::WEBP_FF_CANVAS_WIDTH => WEBP_FF_CANVAS_WIDTH,
+            sys::WEBP_FF_CANVAS_HEIGHT => WEBP_FF_CANVAS_HEIGHT,
+            sys::WEBP_FF_LOOP_COUNT => WEBP_FF_LOOP_COUNT,
+            sys::WEBP_FF_BACKGROUND_COLOR => WEBP_FF_BACKGROUND_COLOR,
+            sys::WEBP_FF_FRAME_COUNT => WEBP_FF_FRAME_COUNT,
+            _ => panic!("WebPFormatFeature::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPFormatFeature {
+        use self::WebPFormatFeature::*;
+
+        match self {
+            WEBP_FF_FORMAT_FLAGS => sys::WEBP_FF_FORMAT_FLAGS,
+            WEBP_FF_CANVAS_WIDTH => sys::WEBP_FF_CANVAS_WIDTH,
+            WEBP_FF_CANVAS_HEIGHT => sys::WEBP_FF_CANVAS_HEIGHT,
+            WEBP_FF_LOOP_COUNT => sys::WEBP_FF_LOOP_COUNT,
+            WEBP_FF_BACKGROUND_COLOR => sys::WEBP_FF_BACKGROUND_COLOR,
+            WEBP_FF_FRAME_COUNT => sys::WEBP_FF_FRAME_COUNT,
+        }
+    }
+}
+
+/// Parsing state of a demuxer, as reported by [`WebPDemuxPartial`].
+///
+/// The variants are ordered by progress.
+///
+/// [`WebPDemuxPartial`]: fn.WebPDemuxPartial.html
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPDemuxState {
+    /// An error occurred while parsing.
+    WEBP_DEMUX_PARSE_ERROR = -1,
+    /// Not enough data to parse the full header.
+    WEBP_DEMUX_PARSING_HEADER = 0,
+    /// Parsed the header, and frames are being parsed.
+    WEBP_DEMUX_PARSED_HEADER = 1,
+    /// Parsed the entire file.
+    WEBP_DEMUX_DONE = 2,
+}
+
+impl WebPDemuxState {
+    pub fn from_raw(raw: sys::WebPDemuxState) -> Self {
+        use self::WebPDemuxState::*;
+
+        match raw {
+            sys::WEBP_DEMUX_PARSE_ERROR => WEBP_DEMUX_PARSE_ERROR,
+            sys::WEBP_DEMUX_PARSING_HEADER => WEBP_DEMUX_PARSING_HEADER,
+            sys::WEBP_DEMUX_PARSED_HEADER => WEBP_DEMUX_PARSED_HEADER,
+            sys::WEBP_DEMUX_DONE => WEBP_DEMUX_DONE,
+            _ => panic!("WebPDemuxState::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPDemuxState {
+        use self::WebPDemuxState::*;
+
+        match self {
+            WEBP_DEMUX_PARSE_ERROR => sys::WEBP_DEMUX_PARSE_ERROR,
+            WEBP_DEMUX_PARSING_HEADER => sys::WEBP_DEMUX_PARSING_HEADER,
+            WEBP_DEMUX_PARSED_HEADER => sys::WEBP_DEMUX_PARSED_HEADER,
+            WEBP_DEMUX_DONE => sys::WEBP_DEMUX_DONE,
+        }
+    }
+}
+
+/// A demuxer parsing a WebP file, which borrows the data for `'a`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::WebPDemux;
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let demuxer = WebPDemux(data).unwrap();
+/// assert_eq!(demuxer.canvas_width(), 128);
+/// assert_eq!(demuxer.canvas_height(), 128);
+/// assert_eq!(demuxer.frame_count(), 1);
+/// ```
+pub struct WebPDemuxer<'a> {
+    ptr: NonNull<sys::WebPDemuxer>,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+unsafe impl<'a> Send for WebPDemuxer<'a> {}
+unsafe impl<'a> Sync for WebPDemuxer<'a> {}
+
+impl<'a> Drop for WebPDemuxer<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPDemuxDelete(self.ptr.as_ptr());
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WebPDemuxer<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("WebPDemuxer")
+    }
+}
+
+impl<'a> WebPDemuxer<'a> {
+    /// Creates `WebPDemuxer` from a raw pointer.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must be a valid demuxer object.
+    /// - The demuxer must be exclusively owned by the caller.
+    /// - The data referenced by the demuxer must outlive `'a`.
+    /// - `raw` must be freeable via `WebPDemuxDelete`.
+    pub unsafe fn from_raw(raw: NonNull<sys::WebPDemuxer>) -> Self {
+        Self {
+            ptr: raw,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_raw(self) -> NonNull<sys::WebPDemuxer> {
+        let ret = self.ptr;
+        mem::forget(self);
+        ret
+    }
+
+    pub fn as_ptr(&self) -> *const sys::WebPDemuxer {
+        self.ptr.as_ptr()
+    }
+
+    pub fn canvas_width(&self) -> u32 {
+        WebPDemuxGetI(self, WebPFormatFeature::WEBP_FF_CANVAS_WIDTH)
+    }
+
+    pub fn canvas_height(&self) -> u32 {
+        WebPDemuxGetI(self, WebPFormatFeature::WEBP_FF_CANVAS_HEIGHT)
+    }
+
+    /// Returns the flags of the `VP8X` chunk, or empty flags for simple
+    /// files.
+    pub fn flags(&self) -> WebPFeatureFlags {
+        WebPFeatureFlags::from_bits_truncate(WebPDemuxGetI(
+            self,
+            WebPFormatFeature::WEBP_FF_FORMAT_FLAGS,
+        ))
+    }
+
+    /// Returns the number of times the animation is played, with 0 meaning
+    /// infinitely. Still images report 1.
+    pub fn loop_count(&self) -> u32 {
+        WebPDemuxGetI(self, WebPFormatFeature::WEBP_FF_LOOP_COUNT)
+    }
+
+    /// Returns the background color of the canvas in `[Blue, Green, Red,
+    /// Alpha]` byte order, read as a little-endian integer. Still images
+    /// report opaque white.
+    pub fn bgcolor(&self) -> u32 {
+        WebPDemuxGetI(self, WebPFormatFeature::WEBP_FF_BACKGROUND_COLOR)
+    }
+
+    /// Returns the number of frames, which is 1 for still images.
+    pub fn frame_count(&self) -> u32 {
+        WebPDemuxGetI(self, WebPFormatFeature::WEBP_FF_FRAME_COUNT)
+    }
+}
+
+/// Parses the full WebP file given by `data`.
+///
+/// ## Errors
+///
+/// Returns `Err` if `data` is not a complete and valid WebP file, or the
+/// allocation failed. Use [`WebPDemuxPartial`] for incomplete files.
+///
+/// [`WebPDemuxPartial`]: fn.WebPDemuxPartial.html
+#[allow(non_snake_case)]
+pub fn WebPDemux(data: &[u8]) -> Result<WebPDemuxer<'_>, WebPSimpleError> {
+    let data = sys::WebPData {
+        bytes: data.as_ptr(),
+        size: data.len(),
+    };
+    let result = unsafe { sys::WebPDemux(&data) };
+    if let Some(result) = NonNull::new(result) {
+        Ok(unsafe { WebPDemuxer::from_raw(result) })
+    } else {
+        Err(WebPSimpleError)
+    }
+}
+
+/// Parses the possibly incomplete WebP file given by `data`, e.g. while it
+/// is being downloaded.
+///
+/// Returns the demuxer along with the parsing state. The demuxer is `None`
+/// on a parse error, or while the RIFF header is incomplete. Otherwise it
+/// reports the canvas as soon as the header is parsed, and the frames
+/// present so far. The image data of a still image may be incomplete, while
+/// animation frames are only reported once their `ANMF` chunk is complete.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPDemuxPartial, WebPDemuxState};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let (demuxer, state) = WebPDemuxPartial(&data[..100]);
+/// assert_eq!(state, WebPDemuxState::WEBP_DEMUX_PARSED_HEADER);
+/// let demuxer = demuxer.unwrap();
+/// assert_eq!(demuxer.canvas_width(), 128);
+/// assert!(!demuxer.frame(1).unwrap().complete);
+///
+/// let (_, state) = WebPDemuxPartial(data);
+/// assert_eq!(state, WebPDemuxState::WEBP_DEMUX_DONE);
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPDemuxPartial(data: &[u8]) -> (Option<WebPDemuxer<'_>>, WebPDemuxState) {
+    let data = sys::WebPData {
+        bytes: data.as_ptr(),
+        size: data.len(),
+    };
+    let mut state = sys::WEBP_DEMUX_PARSE_ERROR;
+    let result = unsafe { sys::WebPDemuxPartial(&data, &mut state) };
+    let demuxer = NonNull::new(result).map(|result| unsafe { WebPDemuxer::from_raw(result) });
+    (demuxer, WebPDemuxState::from_raw(state))
+}
+
+/// Returns the value of `feature` in the file parsed by `dmux`.
+///
+/// The values are only valid for a demuxer which parsed the headers.
+#[allow(non_snake_case)]
+pub fn WebPDemuxGetI(dmux: &WebPDemuxer, feature: WebPFormatFeature) -> u32 {
+    unsafe { sys::WebPDemuxGetI(dmux.as_ptr(), feature.into_raw()) }
+}
+
+/// A frame of a demuxed file, as returned by [`WebPIterator::frame`].
+///
+/// [`WebPIterator::frame`]: struct.WebPIterator.html#method.frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WebPFrame<'a> {
+    /// The frame number, starting from 1.
+    pub frame_num: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Display duration in milliseconds.
+    pub duration: u32,
+    pub dispose_method: WebPMuxAnimDispose,
+    pub blend_method: WebPMuxAnimBlend,
+    pub has_alpha: bool,
+    /// `false` if the data of a partially demuxed frame is still missing.
+    /// Such a frame can be decoded incrementally.
+    pub complete: bool,
+    /// The `ALPH` and `VP8 `/`VP8L` chunks of the frame, which can be passed
+    /// to [`WebPDecodeRGBA`] and its family.
+    ///
+    /// [`WebPDecodeRGBA`]: fn.WebPDecodeRGBA.html
+    pub fragment: &'a [u8],
+}
+
+/// Layout of `WebPIterator` as of libwebp 0.5.0, which removed the fragment
+/// fields. `sys::WebPIterator` has them without the `0_5` feature, so the
+/// struct filled by a newer library is read through this one instead.
+#[cfg(not(feature = "0_5"))]
+#[repr(C)]
+struct IteratorV0_5 {
+    frame_num: c_int,
+    num_frames: c_int,
+    x_offset: c_int,
+    y_offset: c_int,
+    width: c_int,
+    height: c_int,
+    duration: c_int,
+    dispose_method: sys::WebPMuxAnimDispose,
+    complete: c_int,
+    fragment: sys::WebPData,
+    has_alpha: c_int,
+    blend_method: sys::WebPMuxAnimBlend,
+}
+
+macro_rules! frame_from_raw {
+    ($raw:expr) => {
+        WebPFrame {
+            frame_num: $raw.frame_num as u32,
+            x_offset: $raw.x_offset as u32,
+            y_offset: $raw.y_offset as u32,
+            width: $raw.width as u32,
+            height: $raw.height as u32,
+            duration: $raw.duration as u32,
+            dispose_method: WebPMuxAnimDispose::from_raw($raw.dispose_method),
+            blend_method: WebPMuxAnimBlend::from_raw($raw.blend_method),
+            has_alpha: $raw.has_alpha != 0,
+            complete: $raw.complete != 0,
+            fragment: slice::from_raw_parts($raw.fragment.bytes, $raw.fragment.size),
+        }
+    };
+}
+
+/// A cursor over the frames of a [`WebPDemuxer`], released with
+/// `WebPDemuxReleaseIterator`.
+///
+/// Created by [`WebPDemuxGetFrame`], and moved with [`WebPDemuxNextFrame`]
+/// and [`WebPDemuxPrevFrame`].
+///
+/// [`WebPDemuxer`]: struct.WebPDemuxer.html
+/// [`WebPDemuxGetFrame`]: fn.WebPDemuxGetFrame.html
+/// [`WebPDemuxNextFrame`]: fn.WebPDemuxNextFrame.html
+/// [`WebPDemuxPrevFrame`]: fn.WebPDemuxPrevFrame.html
+pub struct WebPIterator<'a> {
+    raw: sys::WebPIterator,
+    _marker: PhantomData<&'a WebPDemuxer<'a>>,
+}
+
+unsafe impl<'a> Send for WebPIterator<'a> {}
+unsafe impl<'a> Sync for WebPIterator<'a> {}
+
+impl<'a> Drop for WebPIterator<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPDemuxReleaseIterator(&mut self.raw);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WebPIterator<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("WebPIterator").field(&self.frame()).finish()
+    }
+}
+
+impl<'a> WebPIterator<'a> {
+    /// Returns the frame the cursor points to.
+    #[cfg(feature = "0_5")]
+    pub fn frame(&self) -> WebPFrame<'a> {
+        unsafe { frame_from_raw!(self.raw) }
+    }
+
+    /// Returns the frame the cursor points to.
+    #[cfg(not(feature = "0_5"))]
+    pub fn frame(&self) -> WebPFrame<'a> {
+        unsafe {
+            if sys::WebPGetDemuxVersion() >= 0x00_05_00 {
+                let raw = &*(&self.raw as *const sys::WebPIterator as *const IteratorV0_5);
+                frame_from_raw!(raw)
+            } else {
+                frame_from_raw!(self.raw)
+            }
+        }
+    }
+
+    pub fn as_raw(&self) -> &sys::WebPIterator {
+        &self.raw
+    }
+}
+
+/// Returns a cursor at frame `frame_number` of `dmux`, counting from 1. The
+/// last frame is returned for 0.
+///
+/// Returns `None` if the frame is not present.
+#[allow(non_snake_case)]
+pub fn WebPDemuxGetFrame<'a>(dmux: &'a WebPDemuxer, frame_number: u32) -> Option<WebPIterator<'a>> {
+    if frame_number > c_int::max_value() as u32 {
+        return None;
+    }
+    let mut iter = WebPIterator {
+        raw: unsafe { mem::zeroed() },
+        _marker: PhantomData,
+    };
+    let result =
+        unsafe { sys::WebPDemuxGetFrame(dmux.as_ptr(), frame_number as c_int, &mut iter.raw) };
+    if result != 0 {
+        Some(iter)
+    } else {
+        None
+    }
+}
+
+/// Moves `iter` to the next frame.
+///
+/// Returns `false` and leaves `iter` unchanged at the last frame.
+#[allow(non_snake_case)]
+pub fn WebPDemuxNextFrame(iter: &mut WebPIterator) -> bool {
+    unsafe { sys::WebPDemuxNextFrame(&mut iter.raw) != 0 }
+}
+
+/// Moves `iter` to the previous frame.
+///
+/// Returns `false` and leaves `iter` unchanged at the first frame.
+#[allow(non_snake_case)]
+pub fn WebPDemuxPrevFrame(iter: &mut WebPIterator) -> bool {
+    unsafe { sys::WebPDemuxPrevFrame(&mut iter.raw) != 0 }
+}
+
+impl<'a> WebPDemuxer<'a> {
+    /// Returns frame `frame_number`, counting from 1.
+    pub fn frame(&self, frame_number: u32) -> Option<WebPFrame<'_>> {
+        if frame_number == 0 {
+            return None;
+        }
+        WebPDemuxGetFrame(self, frame_number).map(|iter| iter.frame())
+    }
+
+    /// Returns an iterator over the frames, from the first to the last.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{WebPDecodeRGBA, WebPDemux};
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let demuxer = WebPDemux(data).unwrap();
+    /// for frame in demuxer.frames() {
+    ///     let (width, height, _) = WebPDecodeRGBA(frame.fragment).unwrap();
+    ///     assert_eq!((width, height), (frame.width, frame.height));
+    /// }
+    /// ```
+    pub fn frames(&self) -> WebPFrames<'_> {
+        let front = WebPDemuxGetFrame(self, 1);
+        let back = WebPDemuxGetFrame(self, 0);
+        let len = match (&front, &back) {
+            (Some(front), Some(back)) => back.frame().frame_num + 1 - front.frame().frame_num,
+            _ => 0,
+        };
+        WebPFrames {
+            front,
+            back,
+            len: len as usize,
+        }
+    }
+}
+
+/// An iterator over the frames of a [`WebPDemuxer`], created by
+/// [`WebPDemuxer::frames`].
+///
+/// [`WebPDemuxer`]: struct.WebPDemuxer.html
+/// [`WebPDemuxer::frames`]: struct.WebPDemuxer.html#method.frames
+#[derive(Debug)]
+pub struct WebPFrames<'a> {
+    front: Option<WebPIterator<'a>>,
+    back: Option<WebPIterator<'a>>,
+    len: usize,
+}
+
+impl<'a> Iterator for WebPFrames<'a> {
+    type Item = WebPFrame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let front = self.front.as_mut()?;
+        let frame = front.frame();
+        self.len -= 1;
+        let _ = WebPDemuxNextFrame(front);
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for WebPFrames<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let back = self.back.as_mut()?;
+        let frame = back.frame();
+        self.len -= 1;
+        let _ = WebPDemuxPrevFrame(back);
+        Some(frame)
+    }
+}
+
+impl<'a> ExactSizeIterator for WebPFrames<'a> {}
+
+/// A cursor over the chunks of a [`WebPDemuxer`] with a given FourCC,
+/// released with `WebPDemuxReleaseChunkIterator`.
+///
+/// Created by [`WebPDemuxGetChunk`], and moved with [`WebPDemuxNextChunk`]
+/// and [`WebPDemuxPrevChunk`].
+///
+/// [`WebPDemuxer`]: struct.WebPDemuxer.html
+/// [`WebPDemuxGetChunk`]: fn.WebPDemuxGetChunk.html
+/// [`WebPDemuxNextChunk`]: fn.WebPDemuxNextChunk.html
+/// [`WebPDemuxPrevChunk`]: fn.WebPDemuxPrevChunk.html
+pub struct WebPChunkIterator<'a> {
+    raw: sys::WebPChunkIterator,
+    _marker: PhantomData<&'a WebPDemuxer<'a>>,
+}
+
+unsafe impl<'a> Send for WebPChunkIterator<'a> {}
+unsafe impl<'a> Sync for WebPChunkIterator<'a> {}
+
+impl<'a> Drop for WebPChunkIterator<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPDemuxReleaseChunkIterator(&mut self.raw);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WebPChunkIterator<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebPChunkIterator")
+            .field("chunk_num", &self.chunk_num())
+            .field("num_chunks", &self.num_chunks())
+            .finish()
+    }
+}
+
+impl<'a> WebPChunkIterator<'a> {
+    /// Returns the number of the current chunk, starting from 1.
+    pub fn chunk_num(&self) -> u32 {
+        self.raw.chunk_num as u32
+    }
+
+    /// Returns the number of chunks with the FourCC.
+    pub fn num_chunks(&self) -> u32 {
+        self.raw.num_chunks as u32
+    }
+
+    /// Returns the payload of the current chunk, without the chunk header.
+    pub fn payload(&self) -> &'a [u8] {
+        unsafe { slice::from_raw_parts(self.raw.chunk.bytes, self.raw.chunk.size) }
+    }
+
+    pub fn as_raw(&self) -> &sys::WebPChunkIterator {
+        &self.raw
+    }
+}
+
+/// Returns a cursor at chunk `chunk_number` among the chunks of `dmux` with
+/// the given `fourcc`, counting from 1. The last chunk is returned for 0.
+///
+/// Returns `None` if the chunk is not present.
+#[allow(non_snake_case)]
+pub fn WebPDemuxGetChunk<'a>(
+    dmux: &'a WebPDemuxer,
+    fourcc: &[u8; 4],
+    chunk_number: u32,
+) -> Option<WebPChunkIterator<'a>> {
+    if chunk_number > c_int::max_value() as u32 {
+        return None;
+    }
+    let mut iter = WebPChunkIterator {
+        raw: unsafe { mem::zeroed() },
+        _marker: PhantomData,
+    };
+    let result = unsafe {
+        sys::WebPDemuxGetChunk(
+            dmux.as_ptr(),
+            fourcc.as_ptr() as *const c_char,
+            chunk_number as c_int,
+            &mut iter.raw,
+        )
+    };
+    if result != 0 {
+        Some(iter)
+    } else {
+        None
+    }
+}
+
+/// Moves `iter` to the next chunk with the same FourCC.
+///
+/// Returns `false` and leaves `iter` unchanged at the last chunk.
+#[allow(non_snake_case)]
+pub fn WebPDemuxNextChunk(iter: &mut WebPChunkIterator) -> bool {
+    unsafe { sys::WebPDemuxNextChunk(&mut iter.raw) != 0 }
+}
+
+/// Moves `iter` to the previous chunk with the same FourCC.
+///
+/// Returns `false` and leaves `iter` unchanged at the first chunk.
+#[allow(non_snake_case)]
+pub fn WebPDemuxPrevChunk(iter: &mut WebPChunkIterator) -> bool {
+    unsafe { sys::WebPDemuxPrevChunk(&mut iter.raw) != 0 }
+}
+
+impl<'a> WebPDemuxer<'a> {
+    /// Returns the payload of the first chunk with the given `fourcc`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPDemux;
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let demuxer = WebPDemux(data).unwrap();
+    /// if let Some(icc_profile) = demuxer.chunk(b"ICCP") {
+    ///     eprintln!("ICC profile: {} bytes", icc_profile.len());
+    /// }
+    /// ```
+    pub fn chunk(&self, fourcc: &[u8; 4]) -> Option<&[u8]> {
+        WebPDemuxGetChunk(self, fourcc, 1).map(|iter| iter.payload())
+    }
+
+    /// Returns an iterator over the payloads of the chunks with the given
+    /// `fourcc`, in file order.
+    pub fn chunks(&self, fourcc: &[u8; 4]) -> WebPChunks<'_> {
+        let front = WebPDemuxGetChunk(self, fourcc, 1);
+        let back = WebPDemuxGetChunk(self, fourcc, 0);
+        let len = front.as_ref().map_or(0, |front| front.num_chunks());
+        WebPChunks {
+            front,
+            back,
+            len: len as usize,
+        }
+    }
+}
+
+/// An iterator over the chunk payloads of a [`WebPDemuxer`], created by
+/// [`WebPDemuxer::chunks`].
+///
+/// [`WebPDemuxer`]: struct.WebPDemuxer.html
+/// [`WebPDemuxer::chunks`]: struct.WebPDemuxer.html#method.chunks
+#[derive(Debug)]
+pub struct WebPChunks<'a> {
+    front: Option<WebPChunkIterator<'a>>,
+    back: Option<WebPChunkIterator<'a>>,
+    len: usize,
+}
+
+impl<'a> Iterator for WebPChunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let front = self.front.as_mut()?;
+        let payload = front.payload();
+        self.len -= 1;
+        let _ = WebPDemuxNextChunk(front);
+        Some(payload)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for WebPChunks<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let back = self.back.as_mut()?;
+        let payload = back.payload();
+        self.len -= 1;
+        let _ = WebPDemuxPrevChunk(back);
+        Some(payload)
+    }
+}
+
+impl<'a> ExactSizeIterator for WebPChunks<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::decode::*;
+    use crate::test_utils::{animation, chunk, riff, u24};
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDemux() {
+        let data = lena();
+        let demuxer = WebPDemux(&data).unwrap();
+        assert_eq!(demuxer.canvas_width(), 128);
+        assert_eq!(demuxer.canvas_height(), 128);
+        assert_eq!(demuxer.flags(), WebPFeatureFlags::empty());
+        assert_eq!(demuxer.frame_count(), 1);
+        assert_eq!(demuxer.loop_count(), 1);
+
+        assert!(WebPDemux(&data[..16]).is_err());
+        assert!(WebPDemux(b"RIFF\0\0\0\0WEBP").is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDemux_animation() {
+        let data = animation();
+        let demuxer = WebPDemux(&data).unwrap();
+        assert_eq!(demuxer.canvas_width(), 8);
+        assert_eq!(demuxer.canvas_height(), 4);
+        assert_eq!(
+            demuxer.flags(),
+            WebPFeatureFlags::ANIMATION | WebPFeatureFlags::ALPHA
+        );
+        assert_eq!(demuxer.frame_count(), 3);
+        assert_eq!(demuxer.loop_count(), 2);
+        assert_eq!(demuxer.bgcolor(), 0xff00_00ff);
+        assert_eq!(
+            WebPDemuxGetI(&demuxer, WebPFormatFeature::WEBP_FF_FRAME_COUNT),
+            3
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        let data = lena();
+        let demuxer = WebPDemux(&data).unwrap();
+        let frames = demuxer.frames().collect::<Vec<_>>();
+        assert_eq!(frames.len(), 1);
+        let frame = frames[0];
+        assert_eq!(frame.frame_num, 1);
+        assert_eq!((frame.x_offset, frame.y_offset), (0, 0));
+        assert_eq!((frame.width, frame.height), (128, 128));
+        assert!(frame.complete);
+        assert!(!frame.has_alpha);
+        assert_eq!(frame.fragment, &data[12..]);
+        assert_eq!(
+            &WebPDecodeRGBA(frame.fragment).unwrap().2[..],
+            &WebPDecodeRGBA(&data).unwrap().2[..]
+        );
+    }
+
+    #[test]
+    fn test_frames_animation() {
+        let data = animation();
+        let demuxer = WebPDemux(&data).unwrap();
+        let frames = demuxer.frames();
+        assert_eq!(frames.len(), 3);
+        let frames = frames.collect::<Vec<_>>();
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.frame_num, i as u32 + 1);
+            assert_eq!((frame.x_offset, frame.y_offset), (2 * i as u32, 0));
+            assert_eq!((frame.width, frame.height), (4, 4));
+            assert_eq!(frame.duration, 100 * (i as u32 + 1));
+            assert_eq!(
+                frame.dispose_method,
+                WebPMuxAnimDispose::WEBP_MUX_DISPOSE_NONE
+            );
+            assert!(frame.complete);
+        }
+        assert_eq!(frames[0].blend_method, WebPMuxAnimBlend::WEBP_MUX_BLEND);
+        assert_eq!(frames[1].blend_method, WebPMuxAnimBlend::WEBP_MUX_NO_BLEND);
+        assert!(!frames[0].has_alpha);
+        assert!(frames[1].has_alpha);
+
+        let (width, height, rgba) = WebPDecodeRGBA(frames[1].fragment).unwrap();
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(&rgba[..4], &[0, 255, 0, 128]);
+
+        let reversed = demuxer.frames().rev().collect::<Vec<_>>();
+        assert_eq!(reversed, frames.iter().rev().cloned().collect::<Vec<_>>());
+
+        let mut both = demuxer.frames();
+        assert_eq!(both.next().unwrap().frame_num, 1);
+        assert_eq!(both.next_back().unwrap().frame_num, 3);
+        assert_eq!(both.len(), 1);
+        assert_eq!(both.next_back().unwrap().frame_num, 2);
+        assert_eq!(both.next(), None);
+        assert_eq!(both.next_back(), None);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDemuxGetFrame() {
+        let data = animation();
+        let demuxer = WebPDemux(&data).unwrap();
+        assert_eq!(demuxer.frame(2).unwrap().duration, 200);
+        assert_eq!(demuxer.frame(0), None);
+        assert_eq!(demuxer.frame(4), None);
+
+        let mut iter = WebPDemuxGetFrame(&demuxer, 0).unwrap();
+        assert_eq!(iter.frame().frame_num, 3);
+        assert!(!WebPDemuxNextFrame(&mut iter));
+        assert_eq!(iter.frame().frame_num, 3);
+        assert!(WebPDemuxPrevFrame(&mut iter));
+        assert!(WebPDemuxPrevFrame(&mut iter));
+        assert_eq!(iter.frame(), demuxer.frame(1).unwrap());
+        assert!(!WebPDemuxPrevFrame(&mut iter));
+        assert!(WebPDemuxNextFrame(&mut iter));
+        assert_eq!(iter.frame().x_offset, 2);
+    }
+
+    fn with_metadata() -> Vec<u8> {
+        let data = lena();
+        let vp8x = [&[0x2c, 0, 0, 0][..], &u24(127), &u24(127)].concat();
+        riff(&[
+            chunk(b"VP8X", &vp8x),
+            chunk(b"ICCP", b"icc"),
+            data[12..].to_vec(),
+            chunk(b"EXIF", b"Exif\0\0"),
+            chunk(b"XMP ", b"<x:xmpmeta/>"),
+            chunk(b"UNKN", b"first"),
+            chunk(b"UNKN", b"second"),
+        ])
+    }
+
+    #[test]
+    fn test_chunks() {
+        let data = with_metadata();
+        let demuxer = WebPDemux(&data).unwrap();
+        assert_eq!(
+            demuxer.flags(),
+            WebPFeatureFlags::ICCP | WebPFeatureFlags::EXIF | WebPFeatureFlags::XMP
+        );
+        assert_eq!(demuxer.chunk(b"ICCP"), Some(&b"icc"[..]));
+        assert_eq!(demuxer.chunk(b"EXIF"), Some(&b"Exif\0\0"[..]));
+        assert_eq!(demuxer.chunk(b"XMP "), Some(&b"<x:xmpmeta/>"[..]));
+        assert_eq!(demuxer.chunk(b"ANIM"), None);
+
+        let unknown = demuxer.chunks(b"UNKN");
+        assert_eq!(unknown.len(), 2);
+        assert_eq!(unknown.collect::<Vec<_>>(), [&b"first"[..], b"second"]);
+        assert_eq!(
+            demuxer.chunks(b"UNKN").rev().collect::<Vec<_>>(),
+            [&b"second"[..], b"first"]
+        );
+        assert_eq!(demuxer.chunks(b"ICCP").count(), 1);
+        assert_eq!(demuxer.chunks(b"ANIM").next(), None);
+
+        // Borrowed from `data` without copying.
+        let payload = demuxer.chunk(b"XMP ").unwrap();
+        let offset = data.windows(12).position(|w| w == payload).unwrap();
+        assert!(std::ptr::eq(payload.as_ptr(), &data[offset]));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDemuxGetChunk() {
+        let data = with_metadata();
+        let demuxer = WebPDemux(&data).unwrap();
+        let mut iter = WebPDemuxGetChunk(&demuxer, b"UNKN", 0).unwrap();
+        assert_eq!((iter.chunk_num(), iter.num_chunks()), (2, 2));
+        assert_eq!(iter.payload(), b"second");
+        assert!(!WebPDemuxNextChunk(&mut iter));
+        assert!(WebPDemuxPrevChunk(&mut iter));
+        assert_eq!(iter.payload(), b"first");
+        assert!(!WebPDemuxPrevChunk(&mut iter));
+        assert!(WebPDemuxGetChunk(&demuxer, b"UNKN", 3).is_none());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPDemuxPartial() {
+        use self::WebPDemuxState::*;
+
+        let data = animation();
+        let mut last_state = WEBP_DEMUX_PARSING_HEADER;
+        let mut last_frame_count = 0;
+        for len in 1..=data.len() {
+            let (demuxer, state) = WebPDemuxPartial(&data[..len]);
+            assert!(state >= last_state, "state went back at {}", len);
+            last_state = state;
+            match state {
+                WEBP_DEMUX_PARSING_HEADER => continue,
+                WEBP_DEMUX_PARSED_HEADER | WEBP_DEMUX_DONE => {}
+                WEBP_DEMUX_PARSE_ERROR => panic!("parse error at {}", len),
+            }
+            let demuxer = demuxer.unwrap();
+            assert_eq!((demuxer.canvas_width(), demuxer.canvas_height()), (8, 4));
+            assert!(demuxer.frame_count() >= last_frame_count);
+            last_frame_count = demuxer.frame_count();
+            for frame in demuxer.frames().filter(|frame| frame.complete) {
+                let (width, height, _) = WebPDecodeRGBA(frame.fragment).unwrap();
+                assert_eq!((width, height), (4, 4));
+            }
+        }
+        assert_eq!(last_state, WEBP_DEMUX_DONE);
+        assert_eq!(last_frame_count, 3);
+
+        let (demuxer, state) = WebPDemuxPartial(&data[..data.len() - 1]);
+        assert_eq!(state, WEBP_DEMUX_PARSED_HEADER);
+        let demuxer = demuxer.unwrap();
+        // A truncated `ANMF` chunk is not reported as a frame yet.
+        assert_eq!(demuxer.frame_count(), 2);
+        assert!(demuxer.frame(2).unwrap().complete);
+        assert_eq!(demuxer.frame(3), None);
+        assert!(WebPDemux(&data[..data.len() - 1]).is_err());
+
+        let (demuxer, state) = WebPDemuxPartial(&data[..16]);
+        assert!(demuxer.is_none());
+        assert_eq!(state, WEBP_DEMUX_PARSING_HEADER);
+
+        let mut invalid = data.clone();
+        invalid[8..12].copy_from_slice(b"WEBQ");
+        let (demuxer, state) = WebPDemuxPartial(&invalid);
+        assert!(demuxer.is_none());
+        assert_eq!(state, WEBP_DEMUX_PARSE_ERROR);
+    }
+}
@@ -1,6 +1,10 @@
 use libwebp_sys as sys;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
 use std::os::raw::*;
 use std::ptr;
+use std::slice;
 
 use crate::boxed::{wrap_bytes, WebpBox};
 use crate::error::WebPSimpleError;
@@ -297,9 +301,11 @@ pub fn WebPEncodeLosslessBGR(
 /// Note these functions, like the lossy versions, use the library's default
 /// settings. For lossless this means `exact` is disabled. RGB values in
 /// transparent areas will be modified to improve compression. To avoid this,
-/// use `WebPEncode()` and set `WebPConfig::exact` to `1`.
+/// use [`WebPEncode`] with [`WebPConfig::set_exact`] (requires the `0_5`
+/// feature).
 ///
-/// (The Rust binding does not yet have the corresponding function.)
+/// [`WebPEncode`]: fn.WebPEncode.html
+/// [`WebPConfig::set_exact`]: struct.WebPConfig.html#method.set_exact
 ///
 /// ## Errors
 ///
@@ -389,6 +395,2086 @@ pub fn WebPEncodeLosslessBGRA(
     }
 }
 
+/// Image characteristics hint for the underlying encoder.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPImageHint {
+    /// default preset.
+    WEBP_HINT_DEFAULT = 0,
+    /// digital picture, like portrait, inner shot
+    WEBP_HINT_PICTURE = 1,
+    /// outdoor photograph, with natural lighting
+    WEBP_HINT_PHOTO = 2,
+    /// Discrete tone image (graph, map-tile etc).
+    WEBP_HINT_GRAPH = 3,
+}
+
+impl WebPImageHint {
+    pub fn from_raw(raw: sys::WebPImageHint) -> Self {
+        use self::WebPImageHint::*;
+
+        match raw {
+            sys::WEBP_HINT_DEFAULT => WEBP_HINT_DEFAULT,
+            sys::WEBP_HINT_PICTURE => WEBP_HINT_PICTURE,
+            sys::WEBP_HINT_PHOTO => WEBP_HINT_PHOTO,
+            sys::WEBP_HINT_GRAPH => WEBP_HINT_GRAPH,
+            _ => panic!("WebPImageHint::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPImageHint {
+        use self::WebPImageHint::*;
+
+        match self {
+            WEBP_HINT_DEFAULT => sys::WEBP_HINT_DEFAULT,
+            WEBP_HINT_PICTURE => sys::WEBP_HINT_PICTURE,
+            WEBP_HINT_PHOTO => sys::WEBP_HINT_PHOTO,
+            WEBP_HINT_GRAPH => sys::WEBP_HINT_GRAPH,
+        }
+    }
+}
+
+/// Predefined settings for [`WebPConfig`], depending on the type of source
+/// picture.
+///
+/// [`WebPConfig`]: struct.WebPConfig.html
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPPreset {
+    /// default preset.
+    WEBP_PRESET_DEFAULT = 0,
+    /// digital picture, like portrait, inner shot
+    WEBP_PRESET_PICTURE = 1,
+    /// outdoor photograph, with natural lighting
+    WEBP_PRESET_PHOTO = 2,
+    /// hand or line drawing, with high-contrast details
+    WEBP_PRESET_DRAWING = 3,
+    /// small-sized colorful images
+    WEBP_PRESET_ICON = 4,
+    /// text-like
+    WEBP_PRESET_TEXT = 5,
+}
+
+impl WebPPreset {
+    pub fn from_raw(raw: sys::WebPPreset) -> Self {
+        use self::WebPPreset::*;
+
+        match raw {
+            sys::WEBP_PRESET_DEFAULT => WEBP_PRESET_DEFAULT,
+            sys::WEBP_PRESET_PICTURE => WEBP_PRESET_PICTURE,
+            sys::WEBP_PRESET_PHOTO => WEBP_PRESET_PHOTO,
+            sys::WEBP_PRESET_DRAWING => WEBP_PRESET_DRAWING,
+            sys::WEBP_PRESET_ICON => WEBP_PRESET_ICON,
+            sys::WEBP_PRESET_TEXT => WEBP_PRESET_TEXT,
+            _ => panic!("WebPPreset::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPPreset {
+        use self::WebPPreset::*;
+
+        match self {
+            WEBP_PRESET_DEFAULT => sys::WEBP_PRESET_DEFAULT,
+            WEBP_PRESET_PICTURE => sys::WEBP_PRESET_PICTURE,
+            WEBP_PRESET_PHOTO => sys::WEBP_PRESET_PHOTO,
+            WEBP_PRESET_DRAWING => sys::WEBP_PRESET_DRAWING,
+            WEBP_PRESET_ICON => sys::WEBP_PRESET_ICON,
+            WEBP_PRESET_TEXT => sys::WEBP_PRESET_TEXT,
+        }
+    }
+}
+
+fn checked_c_int(name: &str, value: u32) -> c_int {
+    assert_eq!(
+        value as c_int as u32, value,
+        "{} {} not within c_int",
+        name, value
+    );
+    assert!(value as c_int >= 0, "{} {} not within c_int", name, value);
+    value as c_int
+}
+
+/// Compression parameters.
+///
+/// Use [`WebPConfigInit`] or [`WebPConfigPreset`] to obtain one. Parameters
+/// are validated by [`WebPEncode`]; see also [`WebPValidateConfig`].
+///
+/// [`WebPConfigInit`]: fn.WebPConfigInit.html
+/// [`WebPConfigPreset`]: fn.WebPConfigPreset.html
+/// [`WebPEncode`]: fn.WebPEncode.html
+/// [`WebPValidateConfig`]: fn.WebPValidateConfig.html
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPConfigPreset, WebPPreset, WebPValidateConfig};
+///
+/// let mut config = WebPConfigPreset(WebPPreset::WEBP_PRESET_PHOTO, 80.0);
+/// config.set_method(6);
+/// assert!(WebPValidateConfig(&config));
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct WebPConfig(sys::WebPConfig, #[cfg(not(feature = "0_6"))] u32);
+
+// Without the `0_6` feature, `sys::WebPConfig` has 28 words. libwebp 1.2.0 or
+// later fills 29, with `use_delta_palette` through `qmax` in place of the
+// padding, hence the extra word.
+
+impl WebPConfig {
+    /// Creates `WebPConfig` from the raw struct.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must have been initialized by `WebPConfigInit` or its family.
+    pub unsafe fn from_raw(raw: sys::WebPConfig) -> Self {
+        let mut config = WebPConfigInit();
+        config.0 = raw;
+        config
+    }
+
+    pub fn into_raw(self) -> sys::WebPConfig {
+        self.0
+    }
+
+    pub fn as_ptr(&self) -> *const sys::WebPConfig {
+        &self.0
+    }
+
+    /// Lossless encoding (`false` = lossy (default), `true` = lossless).
+    pub fn lossless(&self) -> bool {
+        self.0.lossless != 0
+    }
+
+    pub fn set_lossless(&mut self, lossless: bool) {
+        self.0.lossless = lossless as c_int;
+    }
+
+    /// Between 0 and 100. For lossy, 0 gives the smallest size and 100 the
+    /// largest. For lossless, this parameter is the amount of effort put into
+    /// the compression: 0 is the fastest but gives larger files compared to
+    /// the slowest, but best, 100.
+    pub fn quality(&self) -> f32 {
+        self.0.quality
+    }
+
+    pub fn set_quality(&mut self, quality: f32) {
+        self.0.quality = quality as c_float;
+    }
+
+    /// Quality/speed trade-off (0 = fast, 6 = slower-better).
+    pub fn method(&self) -> u32 {
+        self.0.method as u32
+    }
+
+    pub fn set_method(&mut self, method: u32) {
+        self.0.method = checked_c_int("method", method);
+    }
+
+    /// Hint for image type (lossless only for now).
+    pub fn image_hint(&self) -> WebPImageHint {
+        WebPImageHint::from_raw(self.0.image_hint)
+    }
+
+    pub fn set_image_hint(&mut self, image_hint: WebPImageHint) {
+        self.0.image_hint = image_hint.into_raw();
+    }
+
+    /// If non-zero, set the desired target size in bytes.
+    /// Takes precedence over the `quality` parameter.
+    pub fn target_size(&self) -> u32 {
+        self.0.target_size as u32
+    }
+
+    pub fn set_target_size(&mut self, target_size: u32) {
+        self.0.target_size = checked_c_int("target_size", target_size);
+    }
+
+    /// If non-zero, specifies the minimal distortion to try to achieve, in dB.
+    /// Takes precedence over `target_size`.
+    pub fn target_psnr(&self) -> f32 {
+        self.0.target_PSNR
+    }
+
+    pub fn set_target_psnr(&mut self, target_psnr: f32) {
+        self.0.target_PSNR = target_psnr as c_float;
+    }
+
+    /// Maximum number of segments to use, in `1..=4`.
+    pub fn segments(&self) -> u32 {
+        self.0.segments as u32
+    }
+
+    pub fn set_segments(&mut self, segments: u32) {
+        self.0.segments = checked_c_int("segments", segments);
+    }
+
+    /// Spatial Noise Shaping. 0 = off, 100 = maximum.
+    pub fn sns_strength(&self) -> u32 {
+        self.0.sns_strength as u32
+    }
+
+    pub fn set_sns_strength(&mut self, sns_strength: u32) {
+        self.0.sns_strength = checked_c_int("sns_strength", sns_strength);
+    }
+
+    /// Filter strength, from 0 (off) to 100 (strongest).
+    pub fn filter_strength(&self) -> u32 {
+        self.0.filter_strength as u32
+    }
+
+    pub fn set_filter_strength(&mut self, filter_strength: u32) {
+        self.0.filter_strength = checked_c_int("filter_strength", filter_strength);
+    }
+
+    /// Filter sharpness, from 0 (off) to 7 (least sharp).
+    pub fn filter_sharpness(&self) -> u32 {
+        self.0.filter_sharpness as u32
+    }
+
+    pub fn set_filter_sharpness(&mut self, filter_sharpness: u32) {
+        self.0.filter_sharpness = checked_c_int("filter_sharpness", filter_sharpness);
+    }
+
+    /// Filtering type: 0 = simple, 1 = strong (only used if
+    /// `filter_strength > 0` or `autofilter` is on).
+    pub fn filter_type(&self) -> u32 {
+        self.0.filter_type as u32
+    }
+
+    pub fn set_filter_type(&mut self, filter_type: u32) {
+        self.0.filter_type = checked_c_int("filter_type", filter_type);
+    }
+
+    /// Auto adjust filter's strength.
+    pub fn autofilter(&self) -> bool {
+        self.0.autofilter != 0
+    }
+
+    pub fn set_autofilter(&mut self, autofilter: bool) {
+        self.0.autofilter = autofilter as c_int;
+    }
+
+    /// Algorithm for encoding the alpha plane (0 = none, 1 = compressed with
+    /// WebP lossless). Default is 1.
+    pub fn alpha_compression(&self) -> u32 {
+        self.0.alpha_compression as u32
+    }
+
+    pub fn set_alpha_compression(&mut self, alpha_compression: u32) {
+        self.0.alpha_compression = checked_c_int("alpha_compression", alpha_compression);
+    }
+
+    /// Predictive filtering method for alpha plane (0 = none, 1 = fast,
+    /// 2 = best). Default is 1.
+    pub fn alpha_filtering(&self) -> u32 {
+        self.0.alpha_filtering as u32
+    }
+
+    pub fn set_alpha_filtering(&mut self, alpha_filtering: u32) {
+        self.0.alpha_filtering = checked_c_int("alpha_filtering", alpha_filtering);
+    }
+
+    /// Between 0 (smallest size) and 100 (lossless). Default is 100.
+    pub fn alpha_quality(&self) -> u32 {
+        self.0.alpha_quality as u32
+    }
+
+    pub fn set_alpha_quality(&mut self, alpha_quality: u32) {
+        self.0.alpha_quality = checked_c_int("alpha_quality", alpha_quality);
+    }
+
+    /// Number of entropy-analysis passes, in `1..=10`.
+    ///
+    /// When `target_size` or `target_psnr` is set, this is the number of
+    /// iterations spent searching for the quality factor.
+    pub fn pass(&self) -> u32 {
+        self.0.pass as u32
+    }
+
+    pub fn set_pass(&mut self, pass: u32) {
+        self.0.pass = checked_c_int("pass", pass);
+    }
+
+    /// If set, export the compressed picture back.
+    /// In-loop filtering is not applied.
+    pub fn show_compressed(&self) -> bool {
+        self.0.show_compressed != 0
+    }
+
+    pub fn set_show_compressed(&mut self, show_compressed: bool) {
+        self.0.show_compressed = show_compressed as c_int;
+    }
+
+    /// Preprocessing filter (0 = none, 1 = segment-smooth,
+    /// 2 = pseudo-random dithering).
+    pub fn preprocessing(&self) -> u32 {
+        self.0.preprocessing as u32
+    }
+
+    pub fn set_preprocessing(&mut self, preprocessing: u32) {
+        self.0.preprocessing = checked_c_int("preprocessing", preprocessing);
+    }
+
+    /// log2(number of token partitions) in `0..=3`. Default is set to 0 for
+    /// easier progressive decoding.
+    pub fn partitions(&self) -> u32 {
+        self.0.partitions as u32
+    }
+
+    pub fn set_partitions(&mut self, partitions: u32) {
+        self.0.partitions = checked_c_int("partitions", partitions);
+    }
+
+    /// Quality degradation allowed to fit the 512k limit on prediction modes
+    /// coding (0: no degradation, 100: maximum possible degradation).
+    pub fn partition_limit(&self) -> u32 {
+        self.0.partition_limit as u32
+    }
+
+    pub fn set_partition_limit(&mut self, partition_limit: u32) {
+        self.0.partition_limit = checked_c_int("partition_limit", partition_limit);
+    }
+
+    /// If true, compression parameters will be remapped to better match the
+    /// expected output size from JPEG compression.
+    pub fn emulate_jpeg_size(&self) -> bool {
+        self.0.emulate_jpeg_size != 0
+    }
+
+    pub fn set_emulate_jpeg_size(&mut self, emulate_jpeg_size: bool) {
+        self.0.emulate_jpeg_size = emulate_jpeg_size as c_int;
+    }
+
+    /// If true, try and use multi-threaded encoding.
+    pub fn thread_level(&self) -> bool {
+        self.0.thread_level != 0
+    }
+
+    pub fn set_thread_level(&mut self, thread_level: bool) {
+        self.0.thread_level = thread_level as c_int;
+    }
+
+    /// If set, reduce memory usage (but increase CPU use).
+    pub fn low_memory(&self) -> bool {
+        self.0.low_memory != 0
+    }
+
+    pub fn set_low_memory(&mut self, low_memory: bool) {
+        self.0.low_memory = low_memory as c_int;
+    }
+
+    /// Near lossless encoding (0 = max loss, 100 = off).
+    ///
+    /// Only used for lossless encoding. Defaults to 100.
+    #[cfg(feature = "0_5")]
+    pub fn near_lossless(&self) -> u32 {
+        self.0.near_lossless as u32
+    }
+
+    #[cfg(feature = "0_5")]
+    pub fn set_near_lossless(&mut self, near_lossless: u32) {
+        self.0.near_lossless = checked_c_int("near_lossless", near_lossless);
+    }
+
+    /// If set, preserve the exact RGB values under transparent area.
+    /// Otherwise, discard this invisible RGB information for better
+    /// compression.
+    #[cfg(feature = "0_5")]
+    pub fn exact(&self) -> bool {
+        self.0.exact != 0
+    }
+
+    #[cfg(feature = "0_5")]
+    pub fn set_exact(&mut self, exact: bool) {
+        self.0.exact = exact as c_int;
+    }
+
+    /// If set, use delta palettization for lossless encoding (experimental).
+    #[cfg(feature = "0_6")]
+    pub fn use_delta_palette(&self) -> bool {
+        self.0.use_delta_palette != 0
+    }
+
+    #[cfg(feature = "0_6")]
+    pub fn set_use_delta_palette(&mut self, use_delta_palette: bool) {
+        self.0.use_delta_palette = use_delta_palette as c_int;
+    }
+
+    /// If needed, use sharp (and slow) RGB to Y'UV conversion.
+    #[cfg(feature = "0_6")]
+    pub fn use_sharp_yuv(&self) -> bool {
+        self.0.use_sharp_yuv != 0
+    }
+
+    #[cfg(feature = "0_6")]
+    pub fn set_use_sharp_yuv(&mut self, use_sharp_yuv: bool) {
+        self.0.use_sharp_yuv = use_sharp_yuv as c_int;
+    }
+}
+
+impl Default for WebPConfig {
+    fn default() -> Self {
+        WebPConfigInit()
+    }
+}
+
+/// Initializes a [`WebPConfig`] with the default parameters.
+///
+/// Note that the default values are `lossless = false` and `quality = 75`.
+///
+/// [`WebPConfig`]: struct.WebPConfig.html
+///
+/// ## Panics
+///
+/// Panics on a libwebp version mismatch.
+#[allow(non_snake_case)]
+pub fn WebPConfigInit() -> WebPConfig {
+    let mut config: WebPConfig = unsafe { mem::zeroed() };
+    let result = unsafe { sys::WebPConfigInit(&mut config.0) };
+    if result != 0 {
+        config
+    } else {
+        panic!("libwebp version mismatch")
+    }
+}
+
+/// Initializes a [`WebPConfig`] according to a predefined set of parameters
+/// (referred to by `preset`) and a given quality factor.
+///
+/// [`WebPConfig`]: struct.WebPConfig.html
+///
+/// ## Panics
+///
+/// Panics on a libwebp version mismatch.
+#[allow(non_snake_case)]
+pub fn WebPConfigPreset(preset: WebPPreset, quality: f32) -> WebPConfig {
+    let mut config: WebPConfig = unsafe { mem::zeroed() };
+    let result =
+        unsafe { sys::WebPConfigPreset(&mut config.0, preset.into_raw(), quality as c_float) };
+    if result != 0 {
+        config
+    } else {
+        panic!("libwebp version mismatch")
+    }
+}
+
+/// Initializes a [`WebPConfig`] for lossless encoding with the given
+/// compression `level`, between 0 (fastest, lowest compression) and 9
+/// (slower, best compression).
+///
+/// A good default level is 6. Only `lossless`, `method` and `quality` are
+/// changed from the default parameters.
+///
+/// [`WebPConfig`]: struct.WebPConfig.html
+///
+/// ## Errors
+///
+/// Returns `Err` if `level` is out of range.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::WebPConfigLosslessPreset;
+///
+/// let mut config = WebPConfigLosslessPreset(6).unwrap();
+/// assert!(config.lossless());
+/// config.set_exact(true);
+///
+/// assert!(WebPConfigLosslessPreset(10).is_err());
+/// ```
+#[cfg(feature = "0_5")]
+#[allow(non_snake_case)]
+pub fn WebPConfigLosslessPreset(level: u32) -> Result<WebPConfig, WebPSimpleError> {
+    let mut config = WebPConfigInit();
+    let level = if level <= 9 {
+        level as c_int
+    } else {
+        return Err(WebPSimpleError);
+    };
+    let result = unsafe { sys::WebPConfigLosslessPreset(&mut config.0, level) };
+    if result != 0 {
+        Ok(config)
+    } else {
+        Err(WebPSimpleError)
+    }
+}
+
+/// Returns `true` if the parameters in `config` are within range.
+#[allow(non_snake_case)]
+pub fn WebPValidateConfig(config: &WebPConfig) -> bool {
+    (unsafe { sys::WebPValidateConfig(config.as_ptr()) }) != 0
+}
+
+/// Color spaces for YUV(A) input to the encoder.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPEncCSP {
+    /// 4:2:0
+    WEBP_YUV420 = 0,
+    /// alpha channel variant
+    WEBP_YUV420A = 4,
+}
+
+impl WebPEncCSP {
+    pub fn from_raw(raw: sys::WebPEncCSP) -> Self {
+        use self::WebPEncCSP::*;
+
+        match raw {
+            sys::WEBP_YUV420 => WEBP_YUV420,
+            sys::WEBP_YUV420A => WEBP_YUV420A,
+            _ => panic!("WebPEncCSP::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPEncCSP {
+        use self::WebPEncCSP::*;
+
+        match self {
+            WEBP_YUV420 => sys::WEBP_YUV420,
+            WEBP_YUV420A => sys::WEBP_YUV420A,
+        }
+    }
+}
+
+/// Encoding error conditions.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPEncodingError {
+    VP8_ENC_OK = 0,
+    /// memory error allocating objects
+    VP8_ENC_ERROR_OUT_OF_MEMORY = 1,
+    /// memory error while flushing bits
+    VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY = 2,
+    /// a pointer parameter is NULL
+    VP8_ENC_ERROR_NULL_PARAMETER = 3,
+    /// configuration is invalid
+    VP8_ENC_ERROR_INVALID_CONFIGURATION = 4,
+    /// picture has invalid width/height
+    VP8_ENC_ERROR_BAD_DIMENSION = 5,
+    /// partition is bigger than 512k
+    VP8_ENC_ERROR_PARTITION0_OVERFLOW = 6,
+    /// partition is bigger than 16M
+    VP8_ENC_ERROR_PARTITION_OVERFLOW = 7,
+    /// error while flushing bytes
+    VP8_ENC_ERROR_BAD_WRITE = 8,
+    /// file is bigger than 4G
+    VP8_ENC_ERROR_FILE_TOO_BIG = 9,
+    /// abort request by user
+    VP8_ENC_ERROR_USER_ABORT = 10,
+}
+
+impl WebPEncodingError {
+    pub fn from_raw(raw: sys::WebPEncodingError) -> Self {
+        use self::WebPEncodingError::*;
+
+        match raw {
+            sys::VP8_ENC_OK => VP8_ENC_OK,
+            sys::VP8_ENC_ERROR_OUT_OF_MEMORY => VP8_ENC_ERROR_OUT_OF_MEMORY,
+            sys::VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY => VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY,
+            sys::VP8_ENC_ERROR_NULL_PARAMETER => VP8_ENC_ERROR_NULL_PARAMETER,
+            sys::VP8_ENC_ERROR_INVALID_CONFIGURATION => VP8_ENC_ERROR_INVALID_CONFIGURATION,
+            sys::VP8_ENC_ERROR_BAD_DIMENSION => VP8_ENC_ERROR_BAD_DIMENSION,
+            sys::VP8_ENC_ERROR_PARTITION0_OVERFLOW => VP8_ENC_ERROR_PARTITION0_OVERFLOW,
+            sys::VP8_ENC_ERROR_PARTITION_OVERFLOW => VP8_ENC_ERROR_PARTITION_OVERFLOW,
+            sys::VP8_ENC_ERROR_BAD_WRITE => VP8_ENC_ERROR_BAD_WRITE,
+            sys::VP8_ENC_ERROR_FILE_TOO_BIG => VP8_ENC_ERROR_FILE_TOO_BIG,
+            sys::VP8_ENC_ERROR_USER_ABORT => VP8_ENC_ERROR_USER_ABORT,
+            _ => panic!("WebPEncodingError::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPEncodingError {
+        use self::WebPEncodingError::*;
+
+        match self {
+            VP8_ENC_OK => sys::VP8_ENC_OK,
+            VP8_ENC_ERROR_OUT_OF_MEMORY => sys::VP8_ENC_ERROR_OUT_OF_MEMORY,
+            VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY => sys::VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY,
+            VP8_ENC_ERROR_NULL_PARAMETER => sys::VP8_ENC_ERROR_NULL_PARAMETER,
+            VP8_ENC_ERROR_INVALID_CONFIGURATION => sys::VP8_ENC_ERROR_INVALID_CONFIGURATION,
+            VP8_ENC_ERROR_BAD_DIMENSION => sys::VP8_ENC_ERROR_BAD_DIMENSION,
+            VP8_ENC_ERROR_PARTITION0_OVERFLOW => sys::VP8_ENC_ERROR_PARTITION0_OVERFLOW,
+            VP8_ENC_ERROR_PARTITION_OVERFLOW => sys::VP8_ENC_ERROR_PARTITION_OVERFLOW,
+            VP8_ENC_ERROR_BAD_WRITE => sys::VP8_ENC_ERROR_BAD_WRITE,
+            VP8_ENC_ERROR_FILE_TOO_BIG => sys::VP8_ENC_ERROR_FILE_TOO_BIG,
+            VP8_ENC_ERROR_USER_ABORT => sys::VP8_ENC_ERROR_USER_ABORT,
+        }
+    }
+}
+
+impl fmt::Display for WebPEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::WebPEncodingError::*;
+
+        f.write_str(match *self {
+            VP8_ENC_OK => "no error",
+            VP8_ENC_ERROR_OUT_OF_MEMORY => "memory error allocating objects",
+            VP8_ENC_ERROR_BITSTREAM_OUT_OF_MEMORY => "memory error while flushing bits",
+            VP8_ENC_ERROR_NULL_PARAMETER => "a pointer parameter is NULL",
+            VP8_ENC_ERROR_INVALID_CONFIGURATION => "configuration is invalid",
+            VP8_ENC_ERROR_BAD_DIMENSION => "picture has invalid width/height",
+            VP8_ENC_ERROR_PARTITION0_OVERFLOW => "partition is bigger than 512k",
+            VP8_ENC_ERROR_PARTITION_OVERFLOW => "partition is bigger than 16M",
+            VP8_ENC_ERROR_BAD_WRITE => "error while flushing bytes",
+            VP8_ENC_ERROR_FILE_TOO_BIG => "file is bigger than 4G",
+            VP8_ENC_ERROR_USER_ABORT => "abort request by user",
+        })
+    }
+}
+
+impl std::error::Error for WebPEncodingError {}
+
+// Minimum length of a plane with the given geometry.
+// `width` is in units of elements, possibly premultiplied by the pixel width.
+fn plane_len(width: usize, height: u32, stride: u32) -> usize {
+    if height == 0 {
+        0
+    } else {
+        (stride as usize)
+            .checked_mul(height as usize - 1)
+            .and_then(|len| len.checked_add(width))
+            .expect("plane size overflow")
+    }
+}
+
+fn plane_size_check(name: &str, len: usize, width: usize, height: u32, stride: u32) {
+    checked_c_int("stride", stride);
+    assert!(
+        width <= stride as usize,
+        "{} stride too small: {} < {}",
+        name,
+        stride,
+        width
+    );
+    let expected = plane_len(width, height, stride);
+    assert!(
+        expected <= len,
+        "{} plane too small: {} < {}",
+        name,
+        len,
+        expected
+    );
+}
+
+/// The picture to be encoded, and the main exchange structure of the
+/// encoder.
+///
+/// A picture holds its samples either in Y'UV(A) 4:2:0 planes or in a packed
+/// ARGB plane; see [`use_argb`]. The planes are either allocated by libwebp,
+/// borrowed from the caller for `'a`, or handed over by the caller.
+///
+/// [`use_argb`]: #method.use_argb
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPConfigInit, WebPDecodeYUV, WebPEncode, WebPPicture};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let (width, height, stride, uv_stride, mut buf) =
+///     WebPDecodeYUV(data).expect("Invalid WebP data");
+/// let (y, u, v) = buf.yuv_mut();
+/// let mut picture = WebPPicture::from_yuva(width, height, y, stride, u, v, uv_stride, None);
+/// let output = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+/// assert_eq!(&output[..4], b"RIFF");
+/// assert_eq!(&output[8..12], b"WEBP");
+/// ```
+pub struct WebPPicture<'a> {
+    raw: sys::WebPPicture,
+    // Planes handed over by `from_yuva_owned`, which `raw` may point into.
+    owned: Vec<Vec<u8>>,
+    _marker: PhantomData<&'a mut [u8]>,
+}
+
+unsafe impl<'a> Send for WebPPicture<'a> {}
+unsafe impl<'a> Sync for WebPPicture<'a> {}
+
+impl<'a> Drop for WebPPicture<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPPictureFree(&mut self.raw);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WebPPicture<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebPPicture")
+            .field("use_argb", &self.use_argb())
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .finish()
+    }
+}
+
+// Same as WebPPictureInit, except that it panics on version mismatch.
+fn init_picture() -> sys::WebPPicture {
+    let mut raw: sys::WebPPicture = unsafe { mem::zeroed() };
+    let result = unsafe { sys::WebPPictureInit(&mut raw) };
+    if result != 0 {
+        raw
+    } else {
+        panic!("libwebp version mismatch")
+    }
+}
+
+impl WebPPicture<'static> {
+    /// Allocates a Y'UV 4:2:0 picture of the given dimension, using
+    /// `WebPPictureAlloc`.
+    ///
+    /// The alpha plane is allocated as well if `has_alpha` is true.
+    /// The contents of the planes are unspecified; fill them through
+    /// [`y_mut`], [`u_mut`], [`v_mut`] and [`a_mut`].
+    ///
+    /// [`y_mut`]: #method.y_mut
+    /// [`u_mut`]: #method.u_mut
+    /// [`v_mut`]: #method.v_mut
+    /// [`a_mut`]: #method.a_mut
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the dimension is invalid or the allocation failed.
+    pub fn new_yuva(
+        width: u32,
+        height: u32,
+        has_alpha: bool,
+    ) -> Result<WebPPicture<'static>, WebPEncodingError> {
+        let mut raw = init_picture();
+        raw.use_argb = 0;
+        raw.colorspace = if has_alpha {
+            sys::WEBP_YUV420A
+        } else {
+            sys::WEBP_YUV420
+        };
+        raw.width = checked_c_int("width", width);
+        raw.height = checked_c_int("height", height);
+        let mut picture = WebPPicture {
+            raw,
+            owned: Vec::new(),
+            _marker: PhantomData,
+        };
+        let result = unsafe { sys::WebPPictureAlloc(&mut picture.raw) };
+        if result != 0 {
+            Ok(picture)
+        } else {
+            Err(picture.error_code())
+        }
+    }
+
+    /// Allocates an ARGB picture of the given dimension, using
+    /// `WebPPictureAlloc`.
+    ///
+    /// The contents of the plane are unspecified; fill it through
+    /// [`argb_mut`] or the [`import_rgba`] family.
+    ///
+    /// [`argb_mut`]: #method.argb_mut
+    /// [`import_rgba`]: #method.import_rgba
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the dimension is invalid or the allocation failed.
+    pub fn new_argb(width: u32, height: u32) -> Result<WebPPicture<'static>, WebPEncodingError> {
+        let mut raw = init_picture();
+        raw.use_argb = 1;
+        raw.width = checked_c_int("width", width);
+        raw.height = checked_c_int("height", height);
+        let mut picture = WebPPicture {
+            raw,
+            owned: Vec::new(),
+            _marker: PhantomData,
+        };
+        let result = unsafe { sys::WebPPictureAlloc(&mut picture.raw) };
+        if result != 0 {
+            Ok(picture)
+        } else {
+            Err(picture.error_code())
+        }
+    }
+}
+
+impl WebPPicture<'static> {
+    /// Same as [`from_yuva`], but takes ownership of the planes instead of
+    /// borrowing them. The planes are kept alive by the picture, without
+    /// copying.
+    ///
+    /// [`from_yuva`]: #method.from_yuva
+    ///
+    /// ## Panics
+    ///
+    /// Panics when a stride is smaller than the width of the plane, or a
+    /// plane is too small.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{WebPConfigInit, WebPEncode, WebPPicture};
+    ///
+    /// let (width, height) = (4, 3);
+    /// let y = vec![128; 4 * 3];
+    /// let u = vec![64; 2 * 2];
+    /// let v = vec![192; 2 * 2];
+    /// let mut picture = WebPPicture::from_yuva_owned(width, height, y, 4, u, v, 2, None);
+    /// let output = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+    /// assert_eq!(&output[..4], b"RIFF");
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_yuva_owned(
+        width: u32,
+        height: u32,
+        mut y: Vec<u8>,
+        y_stride: u32,
+        mut u: Vec<u8>,
+        mut v: Vec<u8>,
+        uv_stride: u32,
+        a: Option<(Vec<u8>, u32)>,
+    ) -> WebPPicture<'static> {
+        // The heap buffers don't move when the vectors are moved into
+        // `owned`, which outlives the picture's use of them.
+        unsafe fn detach(plane: &mut [u8]) -> &'static mut [u8] {
+            slice::from_raw_parts_mut(plane.as_mut_ptr(), plane.len())
+        }
+
+        let mut a = a;
+        let mut picture = unsafe {
+            WebPPicture::from_yuva(
+                width,
+                height,
+                detach(&mut y),
+                y_stride,
+                detach(&mut u),
+                detach(&mut v),
+                uv_stride,
+                a.as_mut().map(|(a, a_stride)| (detach(a), *a_stride)),
+            )
+        };
+        picture.owned = vec![y, u, v];
+        if let Some((a, _)) = a {
+            picture.owned.push(a);
+        }
+        picture
+    }
+}
+
+impl<'a> WebPPicture<'a> {
+    /// Creates a Y'UV 4:2:0 picture borrowing the given planes.
+    ///
+    /// The dimension of the `u` and `v` planes are both `(width + 1) / 2` and
+    /// `(height + 1) / 2`, sharing `uv_stride`. If `a` is given, the picture
+    /// has an alpha plane with the given stride.
+    ///
+    /// The planes are taken mutably because the encoder may modify samples
+    /// in place, for example under transparent areas.
+    ///
+    /// ## Panics
+    ///
+    /// Panics when a stride is smaller than the width of the plane, or a
+    /// plane is too small.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_yuva(
+        width: u32,
+        height: u32,
+        y: &'a mut [u8],
+        y_stride: u32,
+        u: &'a mut [u8],
+        v: &'a mut [u8],
+        uv_stride: u32,
+        a: Option<(&'a mut [u8], u32)>,
+    ) -> WebPPicture<'a> {
+        let uv_width = (width + 1) / 2;
+        let uv_height = (height + 1) / 2;
+        plane_size_check("y", y.len(), width as usize, height, y_stride);
+        plane_size_check("u", u.len(), uv_width as usize, uv_height, uv_stride);
+        plane_size_check("v", v.len(), uv_width as usize, uv_height, uv_stride);
+        let mut raw = init_picture();
+        raw.use_argb = 0;
+        raw.width = checked_c_int("width", width);
+        raw.height = checked_c_int("height", height);
+        raw.y = y.as_mut_ptr();
+        raw.y_stride = y_stride as c_int;
+        raw.u = u.as_mut_ptr();
+        raw.v = v.as_mut_ptr();
+        raw.uv_stride = uv_stride as c_int;
+        if let Some((a, a_stride)) = a {
+            plane_size_check("a", a.len(), width as usize, height, a_stride);
+            raw.colorspace = sys::WEBP_YUV420A;
+            raw.a = a.as_mut_ptr();
+            raw.a_stride = a_stride as c_int;
+        } else {
+            raw.colorspace = sys::WEBP_YUV420;
+        }
+        WebPPicture {
+            raw,
+            owned: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const sys::WebPPicture {
+        &self.raw
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut sys::WebPPicture {
+        &mut self.raw
+    }
+
+    /// Returns `true` if the samples are held in the ARGB plane, and `false`
+    /// if they are held in the Y'UV(A) planes.
+    pub fn use_argb(&self) -> bool {
+        self.raw.use_argb != 0
+    }
+
+    pub fn colorspace(&self) -> WebPEncCSP {
+        WebPEncCSP::from_raw(self.raw.colorspace)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.raw.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.raw.height as u32
+    }
+
+    pub fn y_stride(&self) -> u32 {
+        self.raw.y_stride as u32
+    }
+
+    pub fn uv_stride(&self) -> u32 {
+        self.raw.uv_stride as u32
+    }
+
+    pub fn a_stride(&self) -> u32 {
+        self.raw.a_stride as u32
+    }
+
+    /// The stride of the ARGB plane, in pixels (not bytes).
+    pub fn argb_stride(&self) -> u32 {
+        self.raw.argb_stride as u32
+    }
+
+    fn y_len(&self) -> usize {
+        plane_len(self.width() as usize, self.height(), self.y_stride())
+    }
+
+    fn uv_len(&self) -> usize {
+        plane_len(
+            (self.width() as usize + 1) / 2,
+            (self.height() + 1) / 2,
+            self.uv_stride(),
+        )
+    }
+
+    fn a_len(&self) -> usize {
+        plane_len(self.width() as usize, self.height(), self.a_stride())
+    }
+
+    fn argb_len(&self) -> usize {
+        plane_len(self.width() as usize, self.height(), self.argb_stride())
+    }
+
+    /// Immutably dereferences to the `y` plane, if any.
+    pub fn y(&self) -> Option<&[u8]> {
+        let len = self.y_len();
+        unsafe { plane(self.raw.y, len) }
+    }
+
+    /// Mutably dereferences to the `y` plane, if any.
+    pub fn y_mut(&mut self) -> Option<&mut [u8]> {
+        let len = self.y_len();
+        unsafe { plane_mut(self.raw.y, len) }
+    }
+
+    /// Immutably dereferences to the `u` plane, if any.
+    pub fn u(&self) -> Option<&[u8]> {
+        let len = self.uv_len();
+        unsafe { plane(self.raw.u, len) }
+    }
+
+    /// Mutably dereferences to the `u` plane, if any.
+    pub fn u_mut(&mut self) -> Option<&mut [u8]> {
+        let len = self.uv_len();
+        unsafe { plane_mut(self.raw.u, len) }
+    }
+
+    /// Immutably dereferences to the `v` plane, if any.
+    pub fn v(&self) -> Option<&[u8]> {
+        let len = self.uv_len();
+        unsafe { plane(self.raw.v, len) }
+    }
+
+    /// Mutably dereferences to the `v` plane, if any.
+    pub fn v_mut(&mut self) -> Option<&mut [u8]> {
+        let len = self.uv_len();
+        unsafe { plane_mut(self.raw.v, len) }
+    }
+
+    /// Immutably dereferences to the `a` (alpha) plane, if any.
+    pub fn a(&self) -> Option<&[u8]> {
+        let len = self.a_len();
+        unsafe { plane(self.raw.a, len) }
+    }
+
+    /// Mutably dereferences to the `a` (alpha) plane, if any.
+    pub fn a_mut(&mut self) -> Option<&mut [u8]> {
+        let len = self.a_len();
+        unsafe { plane_mut(self.raw.a, len) }
+    }
+
+    /// Immutably dereferences to the ARGB plane, if any.
+    ///
+    /// Each pixel is packed as `0xAARRGGBB`.
+    pub fn argb(&self) -> Option<&[u32]> {
+        let len = self.argb_len();
+        unsafe { plane(self.raw.argb, len) }
+    }
+
+    /// Mutably dereferences to the ARGB plane, if any.
+    ///
+    /// Each pixel is packed as `0xAARRGGBB`.
+    pub fn argb_mut(&mut self) -> Option<&mut [u32]> {
+        let len = self.argb_len();
+        unsafe { plane_mut(self.raw.argb, len) }
+    }
+
+    /// Error code for the latest error encountered during encoding.
+    pub fn error_code(&self) -> WebPEncodingError {
+        WebPEncodingError::from_raw(self.raw.error_code)
+    }
+
+    // Converts a boolean result from libwebp, where failures without an error
+    // code are due to invalid parameters.
+    fn picture_result(&self, result: c_int) -> Result<(), WebPEncodingError> {
+        if result != 0 {
+            Ok(())
+        } else {
+            match self.error_code() {
+                WebPEncodingError::VP8_ENC_OK => {
+                    Err(WebPEncodingError::VP8_ENC_ERROR_BAD_DIMENSION)
+                }
+                e => Err(e),
+            }
+        }
+    }
+
+    /// Crops the picture to the rectangle defined by top-left corner pixel
+    /// coordinates `(left, top)` as well as its `width` and `height`.
+    ///
+    /// The picture gets its own new buffers, even if it was a view or had
+    /// borrowed planes. If the picture uses the Y'UV420 colorspace, `left`
+    /// and `top` are snapped to even values.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the rectangle is not fully contained in the picture,
+    /// or the allocation failed.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_yuva(64, 64, false).unwrap();
+    /// picture.crop(16, 16, 32, 24).unwrap();
+    /// assert_eq!((picture.width(), picture.height()), (32, 24));
+    /// assert!(!picture.is_view());
+    /// assert!(picture.crop(16, 16, 32, 24).is_err());
+    /// ```
+    pub fn crop(
+        &mut self,
+        left: u32,
+        top: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), WebPEncodingError> {
+        let result = unsafe {
+            sys::WebPPictureCrop(
+                &mut self.raw,
+                checked_c_int("left", left),
+                checked_c_int("top", top),
+                checked_c_int("width", width),
+                checked_c_int("height", height),
+            )
+        };
+        self.picture_result(result)
+    }
+
+    /// Extracts a view of the rectangle defined by top-left corner pixel
+    /// coordinates `(left, top)` as well as its `width` and `height`.
+    ///
+    /// No pixels are copied: the view points into the samples of `self`,
+    /// which is therefore mutably borrowed while the view is alive. If the
+    /// picture uses the Y'UV420 colorspace, `left` and `top` are snapped to
+    /// even values.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the rectangle is not fully contained in the picture.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_yuva(64, 64, false).unwrap();
+    /// let view = picture.view(16, 16, 32, 24).unwrap();
+    /// assert_eq!((view.width(), view.height()), (32, 24));
+    /// assert!(view.is_view());
+    /// ```
+    ///
+    /// The parent cannot be dropped while the view is in use:
+    ///
+    /// ```rust,compile_fail
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_yuva(64, 64, false).unwrap();
+    /// let view = picture.view(16, 16, 32, 24).unwrap();
+    /// drop(picture);
+    /// assert!(view.is_view());
+    /// ```
+    pub fn view(
+        &mut self,
+        left: u32,
+        top: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<WebPPicture<'_>, WebPEncodingError> {
+        let mut dst = WebPPicture {
+            raw: init_picture(),
+            owned: Vec::new(),
+            _marker: PhantomData,
+        };
+        let result = unsafe {
+            sys::WebPPictureView(
+                &self.raw,
+                checked_c_int("left", left),
+                checked_c_int("top", top),
+                checked_c_int("width", width),
+                checked_c_int("height", height),
+                &mut dst.raw,
+            )
+        };
+        self.picture_result(result)?;
+        Ok(dst)
+    }
+
+    /// Imports R, G, B, R, G, B... ordered samples of the picture's dimension,
+    /// whose rows are `stride` bytes apart.
+    ///
+    /// If [`use_argb`] is true, the samples are copied into a new ARGB plane.
+    /// Otherwise they are converted into new Y'UV(A) planes. Previous buffers
+    /// are freed, if any.
+    ///
+    /// [`use_argb`]: #method.use_argb
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the allocation failed.
+    ///
+    /// ## Panics
+    ///
+    /// Panics when `stride` is too small or `rgb` is too small.
+    ///
+    /// ## Variants
+    ///
+    /// - `import_rgb`
+    /// - [`import_rgba`]
+    /// - [`import_rgbx`]
+    /// - [`import_bgr`]
+    /// - [`import_bgra`]
+    /// - [`import_bgrx`]
+    ///
+    /// [`import_rgba`]: #method.import_rgba
+    /// [`import_rgbx`]: #method.import_rgbx
+    /// [`import_bgr`]: #method.import_bgr
+    /// [`import_bgra`]: #method.import_bgra
+    /// [`import_bgrx`]: #method.import_bgrx
+    pub fn import_rgb(&mut self, rgb: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "rgb",
+            rgb.len(),
+            self.width() as usize * 3,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportRGB(&mut self.raw, rgb.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`import_rgb`], but expecting R, G, B, A, R, G, B, A...
+    /// ordered data.
+    ///
+    /// [`import_rgb`]: #method.import_rgb
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{WebPDecodeRGBA, WebPPicture};
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let (width, height, buf) = WebPDecodeRGBA(data).expect("Invalid WebP data");
+    /// let mut picture = WebPPicture::new_argb(width, height).unwrap();
+    /// picture.import_rgba(&buf, width * 4).unwrap();
+    /// assert_eq!(picture.argb().unwrap()[0] >> 24, 0xFF);
+    /// ```
+    pub fn import_rgba(&mut self, rgba: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "rgba",
+            rgba.len(),
+            self.width() as usize * 4,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportRGBA(&mut self.raw, rgba.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`import_rgb`], but expecting R, G, B, X, R, G, B, X...
+    /// ordered data. The fourth byte is ignored.
+    ///
+    /// [`import_rgb`]: #method.import_rgb
+    pub fn import_rgbx(&mut self, rgbx: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "rgbx",
+            rgbx.len(),
+            self.width() as usize * 4,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportRGBX(&mut self.raw, rgbx.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`import_rgb`], but expecting B, G, R, B, G, R...
+    /// ordered data.
+    ///
+    /// [`import_rgb`]: #method.import_rgb
+    pub fn import_bgr(&mut self, bgr: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "bgr",
+            bgr.len(),
+            self.width() as usize * 3,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportBGR(&mut self.raw, bgr.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`import_rgb`], but expecting B, G, R, A, B, G, R, A...
+    /// ordered data.
+    ///
+    /// [`import_rgb`]: #method.import_rgb
+    pub fn import_bgra(&mut self, bgra: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "bgra",
+            bgra.len(),
+            self.width() as usize * 4,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportBGRA(&mut self.raw, bgra.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`import_rgb`], but expecting B, G, R, X, B, G, R, X...
+    /// ordered data. The fourth byte is ignored.
+    ///
+    /// [`import_rgb`]: #method.import_rgb
+    pub fn import_bgrx(&mut self, bgrx: &[u8], stride: u32) -> Result<(), WebPEncodingError> {
+        plane_size_check(
+            "bgrx",
+            bgrx.len(),
+            self.width() as usize * 4,
+            self.height(),
+            stride,
+        );
+        let result =
+            unsafe { sys::WebPPictureImportBGRX(&mut self.raw, bgrx.as_ptr(), stride as c_int) };
+        self.picture_result(result)
+    }
+
+    /// Converts the ARGB plane to new Y'UV(A) 4:2:0 planes, using
+    /// `WebPPictureARGBToYUVA`.
+    ///
+    /// `colorspace` should be `WEBP_YUV420`; the presence of non-opaque
+    /// alpha values is detected and the colorspace is adjusted accordingly.
+    /// Upon return, [`use_argb`] is false. Note that this conversion is lossy.
+    ///
+    /// [`use_argb`]: #method.use_argb
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if there is no ARGB plane or the allocation failed.
+    ///
+    /// ## Variants
+    ///
+    /// - `argb_to_yuva`
+    /// - [`argb_to_yuva_dithered`]
+    /// - [`sharp_argb_to_yuva`] (`0_6` feature)
+    ///
+    /// [`argb_to_yuva_dithered`]: #method.argb_to_yuva_dithered
+    /// [`sharp_argb_to_yuva`]: #method.sharp_argb_to_yuva
+    pub fn argb_to_yuva(&mut self, colorspace: WebPEncCSP) -> Result<(), WebPEncodingError> {
+        let result = unsafe { sys::WebPPictureARGBToYUVA(&mut self.raw, colorspace.into_raw()) };
+        self.picture_result(result)
+    }
+
+    /// Same as [`argb_to_yuva`], but the conversion is done using
+    /// pseudo-random dithering with a strength `dithering` between 0.0 (no
+    /// dithering) and 1.0 (maximum dithering). This is useful for
+    /// photographic pictures.
+    ///
+    /// [`argb_to_yuva`]: #method.argb_to_yuva
+    pub fn argb_to_yuva_dithered(
+        &mut self,
+        colorspace: WebPEncCSP,
+        dithering: f32,
+    ) -> Result<(), WebPEncodingError> {
+        let result = unsafe {
+            sys::WebPPictureARGBToYUVADithered(
+                &mut self.raw,
+                colorspace.into_raw(),
+                dithering as c_float,
+            )
+        };
+        self.picture_result(result)
+    }
+
+    /// Same as [`argb_to_yuva`], but performs "sharp" RGB to Y'UV420
+    /// downsampling and colorspace conversion.
+    ///
+    /// Downsampling is handled with extra care in case of color clipping.
+    /// This method is roughly 2x slower than [`argb_to_yuva`] but produces
+    /// better and sharper Y'UV representation, e.g. for red edges.
+    ///
+    /// [`argb_to_yuva`]: #method.argb_to_yuva
+    #[cfg(feature = "0_6")]
+    pub fn sharp_argb_to_yuva(&mut self) -> Result<(), WebPEncodingError> {
+        let result = unsafe { sys::WebPPictureSharpARGBToYUVA(&mut self.raw) };
+        self.picture_result(result)
+    }
+
+    /// An older name of [`sharp_argb_to_yuva`].
+    ///
+    /// [`sharp_argb_to_yuva`]: #method.sharp_argb_to_yuva
+    #[cfg(feature = "0_5")]
+    pub fn smart_argb_to_yuva(&mut self) -> Result<(), WebPEncodingError> {
+        let result = unsafe { sys::WebPPictureSmartARGBToYUVA(&mut self.raw) };
+        self.picture_result(result)
+    }
+
+    /// Converts the Y'UV(A) planes to a new ARGB plane, using
+    /// `WebPPictureYUVAToARGB`.
+    ///
+    /// Upon return, [`use_argb`] is true. Note that this conversion is lossy
+    /// too.
+    ///
+    /// [`use_argb`]: #method.use_argb
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if there are no Y'UV(A) planes or the allocation
+    /// failed.
+    pub fn yuva_to_argb(&mut self) -> Result<(), WebPEncodingError> {
+        let result = unsafe { sys::WebPPictureYUVAToARGB(&mut self.raw) };
+        self.picture_result(result)
+    }
+
+    /// Cleans up or smoothens the samples under fully transparent areas, to
+    /// help compressibility (no guarantee, though).
+    ///
+    /// For ARGB pictures, fully transparent 8x8 blocks are flattened to a
+    /// single color. For Y'UV(A) pictures, the luma and chroma samples under
+    /// transparent blocks are smoothened. Left-over pixels on the right and
+    /// bottom edges are not touched.
+    ///
+    /// Note that [`WebPEncode`] already does a similar cleanup unless
+    /// `exact` is set in the config.
+    ///
+    /// [`WebPEncode`]: fn.WebPEncode.html
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_argb(8, 8).unwrap();
+    /// for (i, px) in picture.argb_mut().unwrap().iter_mut().enumerate() {
+    ///     *px = i as u32;
+    /// }
+    /// picture.cleanup_transparent_area();
+    /// assert!(picture.argb().unwrap().iter().all(|&px| px == 0));
+    /// ```
+    pub fn cleanup_transparent_area(&mut self) {
+        unsafe {
+            sys::WebPCleanupTransparentArea(&mut self.raw);
+        }
+    }
+
+    /// Scans the picture for the presence of non fully opaque alpha values.
+    ///
+    /// If it returns `false`, the alpha plane can be ignored altogether. The
+    /// encoder does so by itself, omitting the alpha channel from the output.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_argb(2, 2).unwrap();
+    /// picture.import_rgba(&[255; 16], 8).unwrap();
+    /// assert!(!picture.has_transparency());
+    /// picture.argb_mut().unwrap()[3] = 0x80FFFFFF;
+    /// assert!(picture.has_transparency());
+    /// ```
+    pub fn has_transparency(&self) -> bool {
+        (unsafe { sys::WebPPictureHasTransparency(&self.raw) }) != 0
+    }
+
+    /// Removes the transparency information (if present) by blending the color
+    /// with the background color `background_rgb`, specified as a 24-bit RGB
+    /// triplet `0xRRGGBB`.
+    ///
+    /// After this call, all alpha values are reset to `0xFF`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_argb(1, 1).unwrap();
+    /// picture.argb_mut().unwrap()[0] = 0x00000000;
+    /// picture.blend_alpha(0xFF8000);
+    /// assert_eq!(picture.argb().unwrap()[0], 0xFFFF8000);
+    /// ```
+    pub fn blend_alpha(&mut self, background_rgb: u32) {
+        unsafe {
+            sys::WebPBlendAlpha(&mut self.raw, background_rgb);
+        }
+    }
+
+    /// Returns `true` if the picture doesn't own the memory for its samples,
+    /// i.e. if it is a view or has borrowed planes.
+    pub fn is_view(&self) -> bool {
+        (unsafe { sys::WebPPictureIsView(&self.raw) }) != 0
+    }
+
+    /// Rescales the picture to the new dimension `width` x `height`.
+    ///
+    /// If either `width` or `height` (but not both) is 0, the corresponding
+    /// dimension is calculated preserving the aspect ratio. No gamma
+    /// correction is applied. The picture gets its own new buffers.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the dimension is invalid or the allocation failed.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::WebPPicture;
+    ///
+    /// let mut picture = WebPPicture::new_yuva(64, 48, false).unwrap();
+    /// picture.rescale(32, 0).unwrap();
+    /// assert_eq!((picture.width(), picture.height()), (32, 24));
+    /// ```
+    pub fn rescale(&mut self, width: u32, height: u32) -> Result<(), WebPEncodingError> {
+        let result = unsafe {
+            sys::WebPPictureRescale(
+                &mut self.raw,
+                checked_c_int("width", width),
+                checked_c_int("height", height),
+            )
+        };
+        self.picture_result(result)
+    }
+}
+
+unsafe fn plane<'a, T>(ptr: *const T, len: usize) -> Option<&'a [T]> {
+    if !ptr.is_null() {
+        Some(slice::from_raw_parts(ptr, len))
+    } else {
+        None
+    }
+}
+
+unsafe fn plane_mut<'a, T>(ptr: *mut T, len: usize) -> Option<&'a mut [T]> {
+    if !ptr.is_null() {
+        Some(slice::from_raw_parts_mut(ptr, len))
+    } else {
+        None
+    }
+}
+
+/// Distortion metrics for [`WebPPictureDistortion`].
+///
+/// [`WebPPictureDistortion`]: fn.WebPPictureDistortion.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPDistortionMetric {
+    /// Peak signal-to-noise ratio.
+    Psnr = 0,
+    /// Structural similarity.
+    Ssim = 1,
+    /// Local similarity.
+    Lsim = 2,
+}
+
+/// Return value of [`WebPPictureDistortion`], in dB.
+///
+/// [`WebPPictureDistortion`]: fn.WebPPictureDistortion.html
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct WebPPictureDistortionResult {
+    pub b: f32,
+    pub g: f32,
+    pub r: f32,
+    pub a: f32,
+    /// The overall result over all the channels.
+    pub all: f32,
+}
+
+/// Computes PSNR, SSIM or LSIM distortion metric between two pictures.
+///
+/// The distortion is always measured using ARGB samples. Hence if the input
+/// is Y'UV(A), the picture is internally converted to ARGB (just for the
+/// measurement). Note that this function is rather CPU-intensive.
+///
+/// ## Errors
+///
+/// Returns `Err` if the dimensions differ or the allocation failed.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{
+///     WebPConfigInit, WebPDecodeRGBA, WebPDistortionMetric, WebPEncode, WebPPicture,
+///     WebPPictureDistortion,
+/// };
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let (width, height, buf) = WebPDecodeRGBA(data).expect("Invalid WebP data");
+/// let mut original = WebPPicture::new_argb(width, height).unwrap();
+/// original.import_rgba(&buf, width * 4).unwrap();
+///
+/// let mut config = WebPConfigInit();
+/// config.set_quality(50.0);
+/// let encoded = WebPEncode(&config, &mut original).unwrap();
+/// let (_, _, buf) = WebPDecodeRGBA(&encoded).unwrap();
+/// let mut decoded = WebPPicture::new_argb(width, height).unwrap();
+/// decoded.import_rgba(&buf, width * 4).unwrap();
+///
+/// let psnr = WebPPictureDistortion(&original, &decoded, WebPDistortionMetric::Psnr).unwrap();
+/// assert!(psnr.all > 25.0);
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPPictureDistortion(
+    src: &WebPPicture<'_>,
+    reference: &WebPPicture<'_>,
+    metric: WebPDistortionMetric,
+) -> Result<WebPPictureDistortionResult, WebPSimpleError> {
+    let mut result: [c_float; 5] = [0.0; 5];
+    let ok = unsafe {
+        sys::WebPPictureDistortion(
+            src.as_ptr(),
+            reference.as_ptr(),
+            metric as c_int,
+            result.as_mut_ptr(),
+        )
+    };
+    if ok != 0 {
+        Ok(WebPPictureDistortionResult {
+            b: result[0],
+            g: result[1],
+            r: result[2],
+            a: result[3],
+            all: result[4],
+        })
+    } else {
+        Err(WebPSimpleError)
+    }
+}
+
+/// Helper around [`WebPPictureDistortion`], comparing two buffers of R, G, B,
+/// A, R, G, B, A... ordered samples of the same dimension and stride.
+///
+/// [`WebPPictureDistortion`]: fn.WebPPictureDistortion.html
+///
+/// ## Errors
+///
+/// Returns `Err` if the dimension is invalid or the allocation failed.
+///
+/// ## Panics
+///
+/// Panics when `stride` is too small or a buffer is too small.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPDistortionMetric, distortion_rgba};
+///
+/// let a: &[u8] = &[255, 0, 0, 255, 0, 255, 0, 255];
+/// let b: &[u8] = &[255, 0, 0, 255, 0, 255, 0, 255];
+/// let ssim = distortion_rgba(a, b, 2, 1, 8, WebPDistortionMetric::Ssim).unwrap();
+/// assert!(ssim.all > 30.0);
+/// ```
+pub fn distortion_rgba(
+    src: &[u8],
+    reference: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    metric: WebPDistortionMetric,
+) -> Result<WebPPictureDistortionResult, WebPSimpleError> {
+    let import = |rgba: &[u8]| {
+        let mut picture = WebPPicture::new_argb(width, height).map_err(|_| WebPSimpleError)?;
+        picture
+            .import_rgba(rgba, stride)
+            .map_err(|_| WebPSimpleError)?;
+        Ok(picture)
+    };
+    let src = import(src)?;
+    let reference = import(reference)?;
+    WebPPictureDistortion(&src, &reference, metric)
+}
+
+/// Computes the single distortion for packed planes of samples.
+///
+/// `src` is compared to `reference`, which are both `width` x `height`
+/// planes. `x_step` is the horizontal distance (in bytes) between samples,
+/// and `src_stride` and `ref_stride` are the distances (in bytes) between
+/// rows.
+///
+/// It returns a pair of the raw distortion and the refined metric
+/// (in dB, as in [`WebPPictureDistortion`]).
+///
+/// [`WebPPictureDistortion`]: fn.WebPPictureDistortion.html
+///
+/// ## Errors
+///
+/// Returns `Err` if the allocation failed.
+///
+/// ## Panics
+///
+/// Panics when a stride is too small or a plane is too small.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPDistortionMetric, WebPPlaneDistortion};
+///
+/// // Compare the green channels of two RGB images
+/// let a: &[u8] = &[255, 0, 0, 0, 255, 0];
+/// let b: &[u8] = &[255, 0, 0, 0, 250, 0];
+/// let (_distortion, psnr) = WebPPlaneDistortion(
+///     &a[1..],
+///     6,
+///     &b[1..],
+///     6,
+///     2,
+///     1,
+///     3,
+///     WebPDistortionMetric::Psnr,
+/// )
+/// .unwrap();
+/// assert!(psnr > 30.0);
+/// ```
+#[cfg(feature = "0_6")]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub fn WebPPlaneDistortion(
+    src: &[u8],
+    src_stride: usize,
+    reference: &[u8],
+    ref_stride: usize,
+    width: u32,
+    height: u32,
+    x_step: usize,
+    metric: WebPDistortionMetric,
+) -> Result<(f32, f32), WebPSimpleError> {
+    let width = checked_c_int("width", width);
+    let height = checked_c_int("height", height);
+    let row_len = if width == 0 {
+        0
+    } else {
+        x_step
+            .checked_mul(width as usize - 1)
+            .and_then(|len| len.checked_add(1))
+            .expect("plane size overflow")
+    };
+    let plane_check = |name: &str, len: usize, stride: usize| {
+        assert!(
+            x_step.saturating_mul(width as usize) <= stride,
+            "{} stride too small: {} < {} * {}",
+            name,
+            stride,
+            x_step,
+            width
+        );
+        let expected = if height == 0 {
+            0
+        } else {
+            stride
+                .checked_mul(height as usize - 1)
+                .and_then(|len| len.checked_add(row_len))
+                .expect("plane size overflow")
+        };
+        assert!(
+            expected <= len,
+            "{} plane too small: {} < {}",
+            name,
+            len,
+            expected
+        );
+    };
+    plane_check("src", src.len(), src_stride);
+    plane_check("ref", reference.len(), ref_stride);
+    let mut distortion: c_float = 0.0;
+    let mut result: c_float = 0.0;
+    let ok = unsafe {
+        sys::WebPPlaneDistortion(
+            src.as_ptr(),
+            src_stride,
+            reference.as_ptr(),
+            ref_stride,
+            width,
+            height,
+            x_step,
+            metric as c_int,
+            &mut distortion,
+            &mut result,
+        )
+    };
+    if ok != 0 {
+        Ok((distortion, result))
+    } else {
+        Err(WebPSimpleError)
+    }
+}
+
+extern "C" fn memory_write(
+    data: *const u8,
+    data_size: usize,
+    picture: *const sys::WebPPicture,
+) -> c_int {
+    unsafe { sys::WebPMemoryWrite(data, data_size, picture) }
+}
+
+/// Encoding statistics, filled by [`WebPEncodeWithStats`].
+///
+/// [`WebPEncodeWithStats`]: fn.WebPEncodeWithStats.html
+#[derive(Debug, Clone, Copy)]
+pub struct WebPAuxStats(sys::WebPAuxStats);
+
+impl WebPAuxStats {
+    /// Returns the raw struct.
+    pub fn as_raw(&self) -> &sys::WebPAuxStats {
+        &self.0
+    }
+
+    /// Final size, in bytes.
+    pub fn coded_size(&self) -> u32 {
+        self.0.coded_size as u32
+    }
+
+    /// Peak signal-to-noise ratio for Y/U/V/All/Alpha, in dB.
+    ///
+    /// Only computed by the lossy encoder.
+    pub fn psnr(&self) -> [f32; 5] {
+        self.0.PSNR
+    }
+
+    /// Number of intra4/intra16/skipped macroblocks.
+    pub fn block_count(&self) -> [u32; 3] {
+        let [a, b, c] = self.0.block_count;
+        [a as u32, b as u32, c as u32]
+    }
+
+    /// Quantizer values for each segment.
+    pub fn segment_quant(&self) -> [u32; 4] {
+        let [a, b, c, d] = self.0.segment_quant;
+        [a as u32, b as u32, c as u32, d as u32]
+    }
+
+    /// Size of the transparency data, in bytes.
+    pub fn alpha_data_size(&self) -> u32 {
+        self.0.alpha_data_size as u32
+    }
+
+    /// Final lossless size, in bytes.
+    pub fn lossless_size(&self) -> u32 {
+        self.0.lossless_size as u32
+    }
+
+    /// Number of colors in the palette, if used.
+    pub fn palette_size(&self) -> u32 {
+        self.0.palette_size as u32
+    }
+}
+
+/// Encodes `picture` with the parameters in `config` and returns the WebP
+/// binary data.
+///
+/// `picture` can hold the source samples in both Y'UV(A) or ARGB input.
+/// Y'UV(A) input is encoded directly by the lossy encoder, and ARGB input by
+/// the lossless encoder. Automatic conversion from one format to another is
+/// provided but incurs some loss.
+///
+/// ## Errors
+///
+/// Returns `Err` if `config` is invalid or the encoding failed.
+/// The same error is recorded in [`WebPPicture::error_code`].
+///
+/// [`WebPPicture::error_code`]: struct.WebPPicture.html#method.error_code
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPConfigInit, WebPEncode, WebPPicture};
+///
+/// let mut picture = WebPPicture::new_yuva(16, 16, false).unwrap();
+/// for x in picture.y_mut().unwrap() {
+///     *x = 128;
+/// }
+/// for x in picture.u_mut().unwrap() {
+///     *x = 128;
+/// }
+/// for x in picture.v_mut().unwrap() {
+///     *x = 128;
+/// }
+///
+/// let mut config = WebPConfigInit();
+/// config.set_quality(90.0);
+/// let data = WebPEncode(&config, &mut picture).unwrap();
+/// assert_eq!(&data[..4], b"RIFF");
+/// assert_eq!(&data[8..12], b"WEBP");
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPEncode(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+) -> Result<WebpBox<[u8]>, WebPEncodingError> {
+    encode(config, picture, ptr::null_mut())
+}
+
+/// Same as [`WebPEncode`], but also returns the encoding statistics.
+///
+/// [`WebPEncode`]: fn.WebPEncode.html
+///
+/// ## Errors
+///
+/// Returns `Err` if `config` is invalid or the encoding failed.
+#[allow(non_snake_case)]
+pub fn WebPEncodeWithStats(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+) -> Result<(WebpBox<[u8]>, WebPAuxStats), WebPEncodingError> {
+    let mut stats: sys::WebPAuxStats = unsafe { mem::zeroed() };
+    let output = encode(config, picture, &mut stats)?;
+    Ok((output, WebPAuxStats(stats)))
+}
+
+fn encode(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+    stats: *mut sys::WebPAuxStats,
+) -> Result<WebpBox<[u8]>, WebPEncodingError> {
+    let mut writer: sys::WebPMemoryWriter = unsafe { mem::zeroed() };
+    unsafe { sys::WebPMemoryWriterInit(&mut writer) };
+    picture.raw.writer = Some(memory_write);
+    picture.raw.custom_ptr = &mut writer as *mut sys::WebPMemoryWriter as *mut c_void;
+    picture.raw.stats = stats;
+    let result = unsafe { sys::WebPEncode(config.as_ptr(), &mut picture.raw) };
+    picture.raw.writer = None;
+    picture.raw.custom_ptr = ptr::null_mut();
+    picture.raw.stats = ptr::null_mut();
+    let output = unsafe { wrap_bytes(writer.mem, || writer.size) };
+    if result != 0 {
+        output.map_err(|_| WebPEncodingError::VP8_ENC_ERROR_BAD_WRITE)
+    } else {
+        Err(picture.error_code())
+    }
+}
+
+/// Error returned by [`WebPEncodeTargetSize`] and [`WebPEncodeTargetPSNR`].
+///
+/// Both functions run libwebp's quality search with `config.pass()` raised
+/// to at least 6 iterations, as fewer rarely converge. If the search misses
+/// the target, the picture is encoded once more at the quality bound (0 for
+/// a size target, 100 for a PSNR target) before reporting `TargetNotMet`.
+///
+/// [`WebPEncodeTargetSize`]: fn.WebPEncodeTargetSize.html
+/// [`WebPEncodeTargetPSNR`]: fn.WebPEncodeTargetPSNR.html
+#[derive(Debug)]
+pub enum WebPRateControlError {
+    /// The encoding itself failed.
+    Encoding(WebPEncodingError),
+    /// The target could not be met, even at the lowest (for a size target) or
+    /// highest (for a PSNR target) quality. The result at that quality is
+    /// returned along with its statistics.
+    TargetNotMet {
+        data: WebpBox<[u8]>,
+        stats: Box<WebPAuxStats>,
+    },
+}
+
+impl fmt::Display for WebPRateControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebPRateControlError::Encoding(e) => fmt::Display::fmt(e, f),
+            WebPRateControlError::TargetNotMet { data, stats } => write!(
+                f,
+                "rate control target not met (achieved {} bytes, {} dB)",
+                data.len(),
+                stats.psnr()[3]
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WebPRateControlError {}
+
+impl From<WebPEncodingError> for WebPRateControlError {
+    fn from(e: WebPEncodingError) -> Self {
+        WebPRateControlError::Encoding(e)
+    }
+}
+
+// Minimum number of passes for the quality search to converge.
+const RATE_CONTROL_MIN_PASS: u32 = 6;
+
+/// Encodes `picture` lossily into at most `target_size` bytes, and returns
+/// the data along with the achieved size and PSNR.
+///
+/// The quality factor is searched by libwebp starting from
+/// `config.quality()`, using `config.pass()` iterations (raised to at least
+/// 6). If the search misses, the picture is encoded again at quality 0.
+/// `target_size` and `target_psnr` in `config` are overridden.
+///
+/// ## Errors
+///
+/// Returns [`WebPRateControlError::TargetNotMet`] if the output is still
+/// larger than `target_size` at quality 0, or
+/// [`WebPRateControlError::Encoding`] if the encoding failed. As the lossless
+/// encoder doesn't support rate control, a lossless `config` is rejected with
+/// `VP8_ENC_ERROR_INVALID_CONFIGURATION`.
+///
+/// [`WebPRateControlError::TargetNotMet`]: enum.WebPRateControlError.html#variant.TargetNotMet
+/// [`WebPRateControlError::Encoding`]: enum.WebPRateControlError.html#variant.Encoding
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPConfigInit, WebPDecodeRGBA, WebPEncodeTargetSize, WebPPicture};
+///
+/// let (width, height, rgba) = WebPDecodeRGBA(include_bytes!("lena.webp")).unwrap();
+/// let mut picture = WebPPicture::new_argb(width, height).unwrap();
+/// picture.import_rgba(&rgba, width * 4).unwrap();
+///
+/// let (data, stats) = WebPEncodeTargetSize(&WebPConfigInit(), &mut picture, 4000).unwrap();
+/// assert!(data.len() <= 4000);
+/// assert_eq!(stats.coded_size() as usize, data.len());
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPEncodeTargetSize(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+    target_size: u32,
+) -> Result<(WebpBox<[u8]>, WebPAuxStats), WebPRateControlError> {
+    rate_control(
+        config,
+        picture,
+        |config| config.set_target_size(target_size),
+        0.0,
+        |data, _| data.len() <= target_size as usize,
+    )
+}
+
+/// Encodes `picture` lossily with at least `target_psnr` dB of PSNR, and
+/// returns the data along with the achieved size and PSNR.
+///
+/// The quality factor is searched the same way as in
+/// [`WebPEncodeTargetSize`], falling back to quality 100.
+///
+/// [`WebPEncodeTargetSize`]: fn.WebPEncodeTargetSize.html
+///
+/// ## Errors
+///
+/// Returns [`WebPRateControlError::TargetNotMet`] if the overall PSNR is
+/// still below `target_psnr` at quality 100, or
+/// [`WebPRateControlError::Encoding`] if the encoding failed, including for a
+/// lossless `config` as in [`WebPEncodeTargetSize`].
+///
+/// [`WebPRateControlError::TargetNotMet`]: enum.WebPRateControlError.html#variant.TargetNotMet
+/// [`WebPRateControlError::Encoding`]: enum.WebPRateControlError.html#variant.Encoding
+#[allow(non_snake_case)]
+pub fn WebPEncodeTargetPSNR(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+    target_psnr: f32,
+) -> Result<(WebpBox<[u8]>, WebPAuxStats), WebPRateControlError> {
+    rate_control(
+        config,
+        picture,
+        |config| config.set_target_psnr(target_psnr),
+        100.0,
+        |_, stats| stats.psnr()[3] >= target_psnr,
+    )
+}
+
+// Runs libwebp's quality search with the target set by `set_target`. If
+// the result doesn't satisfy `met`, encodes once more without a target at
+// `bound_quality`, the quality most likely to satisfy it.
+fn rate_control<T, M>(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+    set_target: T,
+    bound_quality: f32,
+    met: M,
+) -> Result<(WebpBox<[u8]>, WebPAuxStats), WebPRateControlError>
+where
+    T: FnOnce(&mut WebPConfig),
+    M: Fn(&[u8], &WebPAuxStats) -> bool,
+{
+    if config.lossless() {
+        return Err(WebPEncodingError::VP8_ENC_ERROR_INVALID_CONFIGURATION.into());
+    }
+    let mut config = *config;
+    config.set_target_size(0);
+    config.set_target_psnr(0.0);
+    let mut bound_config = config;
+    bound_config.set_quality(bound_quality);
+
+    if config.pass() < RATE_CONTROL_MIN_PASS {
+        config.set_pass(RATE_CONTROL_MIN_PASS);
+    }
+    set_target(&mut config);
+    let (data, stats) = WebPEncodeWithStats(&config, picture)?;
+    if met(&data, &stats) {
+        return Ok((data, stats));
+    }
+
+    let (data, stats) = WebPEncodeWithStats(&bound_config, picture)?;
+    if met(&data, &stats) {
+        Ok((data, stats))
+    } else {
+        Err(WebPRateControlError::TargetNotMet {
+            data,
+            stats: Box::new(stats),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,6 +2490,583 @@ mod tests {
         let (width, height, buf) = WebPDecodeRGB(&lena()).unwrap();
         assert_eq!(width, 128);
         assert_eq!(height, 128);
-        WebPEncodeRGB(&buf, width, height, width, 50.0).unwrap();
+        WebPEncodeRGB(&buf, width, height, width * 3, 50.0).unwrap();
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_yuv() {
+        let (width, height, stride, uv_stride, mut buf) = WebPDecodeYUV(&lena()).unwrap();
+        let data = {
+            let (y, u, v) = buf.yuv_mut();
+            let mut picture =
+                WebPPicture::from_yuva(width, height, y, stride, u, v, uv_stride, None);
+            assert!(!picture.use_argb());
+            assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420);
+            let mut config = WebPConfigInit();
+            config.set_quality(90.0);
+            WebPEncode(&config, &mut picture).unwrap()
+        };
+        assert_eq!(WebPGetInfo(&data).unwrap(), (128, 128));
+
+        let (_, _, _, _, buf2) = WebPDecodeYUV(&data).unwrap();
+        let diff = buf
+            .y()
+            .iter()
+            .zip(buf2.y())
+            .map(|(&a, &b)| (a as i64 - b as i64).abs())
+            .sum::<i64>()
+            / buf.y().len() as i64;
+        assert!(diff < 8, "diff = {}", diff);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_yuva() {
+        let mut picture = WebPPicture::new_yuva(3, 5, true).unwrap();
+        assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420A);
+        assert_eq!(picture.y().unwrap().len(), 3 * 5);
+        assert_eq!(picture.u().unwrap().len(), 2 * 3);
+        assert_eq!(picture.v().unwrap().len(), 2 * 3);
+        assert_eq!(picture.a().unwrap().len(), 3 * 5);
+        for x in picture.y_mut().unwrap() {
+            *x = 200;
+        }
+        for x in picture.u_mut().unwrap() {
+            *x = 128;
+        }
+        for x in picture.v_mut().unwrap() {
+            *x = 128;
+        }
+        for x in picture.a_mut().unwrap() {
+            *x = 0;
+        }
+        let data = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+        let (width, height, buf) = WebPDecodeRGBA(&data).unwrap();
+        assert_eq!((width, height), (3, 5));
+        assert!(buf.chunks(4).all(|px| px[3] == 0));
+    }
+
+    #[test]
+    fn test_from_yuva_owned() {
+        let (width, height, stride, uv_stride, mut buf) = WebPDecodeYUV(&lena()).unwrap();
+        let (y, u, v) = buf.yuv_mut();
+        let (y_owned, u_owned, v_owned) = (y.to_vec(), u.to_vec(), v.to_vec());
+        let expected = {
+            let mut picture =
+                WebPPicture::from_yuva(width, height, y, stride, u, v, uv_stride, None);
+            WebPEncode(&WebPConfigInit(), &mut picture).unwrap()
+        };
+        let (y_ptr, a_ptr) = (y_owned.as_ptr(), vec![255; 128 * 128]);
+        let mut picture = WebPPicture::from_yuva_owned(
+            width, height, y_owned, stride, u_owned, v_owned, uv_stride, None,
+        );
+        assert_eq!(picture.y().unwrap().as_ptr(), y_ptr);
+        let data = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+        assert_eq!(&data[..], &expected[..]);
+
+        let (y, u, v) = buf.yuv_mut();
+        let mut picture = WebPPicture::from_yuva_owned(
+            width,
+            height,
+            y.to_vec(),
+            stride,
+            u.to_vec(),
+            v.to_vec(),
+            uv_stride,
+            Some((a_ptr, 128)),
+        );
+        assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420A);
+        assert!(WebPEncode(&WebPConfigInit(), &mut picture).is_ok());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_invalid_config() {
+        let mut picture = WebPPicture::new_yuva(4, 4, false).unwrap();
+        let mut config = WebPConfigInit();
+        config.set_method(100);
+        assert!(!WebPValidateConfig(&config));
+        assert_eq!(
+            WebPEncode(&config, &mut picture).unwrap_err(),
+            WebPEncodingError::VP8_ENC_ERROR_INVALID_CONFIGURATION
+        );
+    }
+
+    #[cfg(feature = "0_5")]
+    fn transparent_payload_rgba() -> Vec<u8> {
+        (0..16 * 16)
+            .flat_map(|i: u32| {
+                let alpha = if i % 3 == 0 { 0 } else { 255 };
+                vec![(i * 7) as u8, (i * 13) as u8, (i * 31) as u8, alpha]
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "0_5")]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_lossless_exact() {
+        let rgba = transparent_payload_rgba();
+        let mut config = WebPConfigLosslessPreset(6).unwrap();
+        config.set_exact(true);
+        assert!(config.exact());
+        let mut picture = WebPPicture::new_argb(16, 16).unwrap();
+        picture.import_rgba(&rgba, 16 * 4).unwrap();
+        let data = WebPEncode(&config, &mut picture).unwrap();
+        let (width, height, buf) = WebPDecodeRGBA(&data).unwrap();
+        assert_eq!((width, height), (16, 16));
+        assert_eq!(&buf[..], &rgba[..]);
+
+        config.set_exact(false);
+        let mut picture = WebPPicture::new_argb(16, 16).unwrap();
+        picture.import_rgba(&rgba, 16 * 4).unwrap();
+        let data = WebPEncode(&config, &mut picture).unwrap();
+        let (_, _, buf) = WebPDecodeRGBA(&data).unwrap();
+        assert_ne!(&buf[..], &rgba[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "0_5")]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_near_lossless() {
+        let (width, height, rgba) = WebPDecodeRGBA(include_bytes!("lena.webp")).unwrap();
+        let encode = |near_lossless| {
+            let mut config = WebPConfigLosslessPreset(6).unwrap();
+            config.set_near_lossless(near_lossless);
+            assert_eq!(config.near_lossless(), near_lossless);
+            let mut picture = WebPPicture::new_argb(width, height).unwrap();
+            picture.import_rgba(&rgba, width * 4).unwrap();
+            WebPEncode(&config, &mut picture).unwrap()
+        };
+        let lossless = encode(100);
+        let near_lossless = encode(40);
+        assert!(near_lossless.len() < lossless.len());
+        assert_eq!(&WebPDecodeRGBA(&lossless).unwrap().2[..], &rgba[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "0_5")]
+    #[allow(non_snake_case)]
+    fn test_WebPConfigLosslessPreset() {
+        for level in 0..=9 {
+            let config = WebPConfigLosslessPreset(level).unwrap();
+            assert!(config.lossless());
+            assert!(WebPValidateConfig(&config));
+        }
+        assert!(WebPConfigLosslessPreset(10).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "0_6")]
+    #[allow(non_snake_case)]
+    fn test_WebPEncode_delta_palette() {
+        let rgba = transparent_payload_rgba();
+        let mut config = WebPConfigLosslessPreset(6).unwrap();
+        config.set_exact(true);
+        config.set_use_delta_palette(true);
+        assert!(config.use_delta_palette());
+        let mut picture = WebPPicture::new_argb(16, 16).unwrap();
+        picture.import_rgba(&rgba, 16 * 4).unwrap();
+        let data = WebPEncode(&config, &mut picture).unwrap();
+        assert_eq!(&WebPDecodeRGBA(&data).unwrap().2[..], &rgba[..]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncodeWithStats() {
+        let mut picture = lena_argb_picture();
+        let (data, stats) = WebPEncodeWithStats(&WebPConfigInit(), &mut picture).unwrap();
+        assert_eq!(stats.coded_size() as usize, data.len());
+        assert!(stats.psnr()[3] > 20.0);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncodeTargetSize() {
+        let config = WebPConfigInit();
+        for &target in &[3000, 6000] {
+            let mut picture = lena_argb_picture();
+            let (data, stats) = WebPEncodeTargetSize(&config, &mut picture, target).unwrap();
+            assert!(data.len() <= target as usize);
+            assert_eq!(stats.coded_size() as usize, data.len());
+        }
+
+        // Smallest output, at quality 0.
+        let mut min_config = WebPConfigInit();
+        min_config.set_quality(0.0);
+        let min_size = WebPEncode(&min_config, &mut lena_argb_picture())
+            .unwrap()
+            .len() as u32;
+
+        // Reachable only at the bound, which the search alone stops short of.
+        let mut picture = lena_argb_picture();
+        let (data, _) = WebPEncodeTargetSize(&config, &mut picture, min_size).unwrap();
+        assert!(data.len() <= min_size as usize);
+
+        // Impossible even at quality 0.
+        let mut picture = lena_argb_picture();
+        match WebPEncodeTargetSize(&config, &mut picture, min_size - 1) {
+            Err(WebPRateControlError::TargetNotMet { data, stats }) => {
+                assert_eq!(data.len() as u32, min_size);
+                assert_eq!(stats.coded_size() as usize, data.len());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncodeTargetPSNR() {
+        let config = WebPConfigInit();
+        let mut picture = lena_argb_picture();
+        let (_, stats) = WebPEncodeTargetPSNR(&config, &mut picture, 30.0).unwrap();
+        assert!(stats.psnr()[3] >= 30.0);
+
+        let mut picture = lena_argb_picture();
+        match WebPEncodeTargetPSNR(&config, &mut picture, 99.0) {
+            Err(WebPRateControlError::TargetNotMet { stats, .. }) => {
+                assert!(stats.psnr()[3] < 99.0);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut config = WebPConfigInit();
+        config.set_lossless(true);
+        let mut picture = lena_argb_picture();
+        match WebPEncodeTargetPSNR(&config, &mut picture, 30.0) {
+            Err(WebPRateControlError::Encoding(e)) => {
+                assert_eq!(e, WebPEncodingError::VP8_ENC_ERROR_INVALID_CONFIGURATION);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn lena_yuv_picture_test<F>(f: F)
+    where
+        F: FnOnce(WebPPicture<'_>),
+    {
+        let (width, height, stride, uv_stride, mut buf) = WebPDecodeYUV(&lena()).unwrap();
+        let (y, u, v) = buf.yuv_mut();
+        f(WebPPicture::from_yuva(
+            width, height, y, stride, u, v, uv_stride, None,
+        ));
+    }
+
+    #[test]
+    fn test_picture_crop() {
+        lena_yuv_picture_test(|mut picture| {
+            assert!(picture.is_view());
+            let y = picture.y().unwrap()[10 * 128 + 20..][..8].to_vec();
+            picture.crop(20, 10, 40, 30).unwrap();
+            assert!(!picture.is_view());
+            assert_eq!((picture.width(), picture.height()), (40, 30));
+            assert_eq!(&picture.y().unwrap()[..8], &y[..]);
+            assert_eq!(
+                picture.crop(20, 10, 40, 30).unwrap_err(),
+                WebPEncodingError::VP8_ENC_ERROR_BAD_DIMENSION
+            );
+            let data = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+            assert_eq!(WebPGetInfo(&data).unwrap(), (40, 30));
+        });
+    }
+
+    #[test]
+    fn test_picture_view() {
+        lena_yuv_picture_test(|mut picture| {
+            let y = picture.y().unwrap()[10 * 128 + 20..][..8].to_vec();
+            let data = {
+                let mut view = picture.view(20, 10, 40, 30).unwrap();
+                assert!(view.is_view());
+                assert_eq!((view.width(), view.height()), (40, 30));
+                assert_eq!(view.y_stride(), 128);
+                assert_eq!(&view.y().unwrap()[..8], &y[..]);
+                assert!(view.view(30, 20, 20, 20).is_err());
+                WebPEncode(&WebPConfigInit(), &mut view).unwrap()
+            };
+            assert_eq!(WebPGetInfo(&data).unwrap(), (40, 30));
+            assert_eq!((picture.width(), picture.height()), (128, 128));
+        });
+    }
+
+    #[test]
+    fn test_picture_rescale() {
+        lena_yuv_picture_test(|mut picture| {
+            picture.rescale(64, 0).unwrap();
+            assert!(!picture.is_view());
+            assert_eq!((picture.width(), picture.height()), (64, 64));
+            assert_eq!(picture.y().unwrap().len(), 64 * 64);
+            assert!(picture.rescale(0, 0).is_err());
+            let data = WebPEncode(&WebPConfigInit(), &mut picture).unwrap();
+            assert_eq!(WebPGetInfo(&data).unwrap(), (64, 64));
+        });
+    }
+
+    fn lena_argb_picture() -> WebPPicture<'static> {
+        let (width, height, buf) = WebPDecodeRGBA(&lena()).unwrap();
+        let mut picture = WebPPicture::new_argb(width, height).unwrap();
+        picture.import_rgba(&buf, width * 4).unwrap();
+        picture
+    }
+
+    // A picture of 2px-wide red stripes on a black background.
+    #[cfg(feature = "0_6")]
+    fn red_lines_picture() -> WebPPicture<'static> {
+        let mut picture = WebPPicture::new_argb(32, 32).unwrap();
+        for (i, px) in picture.argb_mut().unwrap().iter_mut().enumerate() {
+            *px = if i % 4 < 2 { 0xFFFF0000 } else { 0xFF000000 };
+        }
+        picture
+    }
+
+    fn argb_roundtrip_error(picture: &mut WebPPicture<'_>) -> u64 {
+        let orig = picture.argb().unwrap().to_vec();
+        picture.yuva_to_argb().unwrap();
+        orig.iter()
+            .zip(picture.argb().unwrap())
+            .map(|(&a, &b)| {
+                (0..4)
+                    .map(|i| {
+                        ((a >> (i * 8)) as u8 as i64 - (b >> (i * 8)) as u8 as i64).abs() as u64
+                    })
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_picture_import() {
+        let (width, height, buf) = WebPDecodeBGR(&lena()).unwrap();
+        let mut picture = WebPPicture::new_argb(width, height).unwrap();
+        picture.import_bgr(&buf, width * 3).unwrap();
+        assert!(picture.use_argb());
+        assert_eq!(picture.argb().unwrap()[0], 0xFFE29E71);
+
+        let mut picture = WebPPicture::new_yuva(width, height, false).unwrap();
+        picture.import_bgr(&buf, width * 3).unwrap();
+        assert!(!picture.use_argb());
+        assert!(picture.argb().is_none());
+        assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420);
+    }
+
+    #[test]
+    #[should_panic(expected = "rgba stride too small")]
+    fn test_picture_import_small_stride() {
+        let mut picture = WebPPicture::new_argb(4, 4).unwrap();
+        let _ = picture.import_rgba(&[0; 64], 12);
+    }
+
+    #[test]
+    fn test_picture_argb_to_yuva() {
+        let mut picture = lena_argb_picture();
+        picture.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap();
+        assert!(!picture.use_argb());
+        assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420);
+        assert!(picture.a().is_none());
+        let y = picture.y().unwrap()[0];
+        assert!((160..=170).contains(&y), "y = {}", y);
+
+        let mut picture = lena_argb_picture();
+        picture
+            .argb_to_yuva_dithered(WebPEncCSP::WEBP_YUV420, 1.0)
+            .unwrap();
+        assert!(!picture.use_argb());
+
+        let mut picture = WebPPicture::new_yuva(4, 4, false).unwrap();
+        assert_eq!(
+            picture.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap_err(),
+            WebPEncodingError::VP8_ENC_ERROR_NULL_PARAMETER
+        );
+    }
+
+    #[test]
+    fn test_picture_argb_to_yuva_alpha() {
+        let mut picture = WebPPicture::new_argb(4, 4).unwrap();
+        for px in picture.argb_mut().unwrap() {
+            *px = 0x80FFFFFF;
+        }
+        picture.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap();
+        assert_eq!(picture.colorspace(), WebPEncCSP::WEBP_YUV420A);
+        assert!(picture.a().unwrap().iter().all(|&a| a == 0x80));
+    }
+
+    #[test]
+    #[cfg(feature = "0_6")]
+    fn test_picture_sharp_argb_to_yuva() {
+        let mut plain = red_lines_picture();
+        plain.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap();
+        let mut sharp = red_lines_picture();
+        sharp.sharp_argb_to_yuva().unwrap();
+        assert!(!sharp.use_argb());
+        assert!(argb_roundtrip_error(&mut sharp) < argb_roundtrip_error(&mut plain));
+
+        let mut config = WebPConfigInit();
+        config.set_use_sharp_yuv(true);
+        WebPEncode(&config, &mut red_lines_picture()).unwrap();
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPPictureDistortion() {
+        let original = lena_argb_picture();
+        let same = lena_argb_picture();
+        let psnr = WebPPictureDistortion(&original, &same, WebPDistortionMetric::Psnr).unwrap();
+        assert_eq!(psnr.all, 99.0);
+
+        let mut yuv = lena_argb_picture();
+        yuv.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap();
+        for &metric in &[
+            WebPDistortionMetric::Psnr,
+            WebPDistortionMetric::Ssim,
+            WebPDistortionMetric::Lsim,
+        ] {
+            let result = WebPPictureDistortion(&original, &yuv, metric).unwrap();
+            assert!(result.all > 20.0, "{:?}: {:?}", metric, result);
+            assert!(result.all < 99.0, "{:?}: {:?}", metric, result);
+            // No alpha difference
+            assert_eq!(result.a, 99.0, "{:?}: {:?}", metric, result);
+        }
+
+        let small = WebPPicture::new_argb(4, 4).unwrap();
+        assert!(WebPPictureDistortion(&original, &small, WebPDistortionMetric::Psnr).is_err());
+    }
+
+    #[test]
+    fn test_distortion_rgba() {
+        let (width, height, buf) = WebPDecodeRGBA(&lena()).unwrap();
+        let data = WebPEncodeRGBA(&buf, width, height, width * 4, 10.0).unwrap();
+        let (_, _, buf10) = WebPDecodeRGBA(&data).unwrap();
+        let data = WebPEncodeRGBA(&buf, width, height, width * 4, 90.0).unwrap();
+        let (_, _, buf90) = WebPDecodeRGBA(&data).unwrap();
+        let psnr10 = distortion_rgba(
+            &buf,
+            &buf10,
+            width,
+            height,
+            width * 4,
+            WebPDistortionMetric::Psnr,
+        )
+        .unwrap();
+        let psnr90 = distortion_rgba(
+            &buf,
+            &buf90,
+            width,
+            height,
+            width * 4,
+            WebPDistortionMetric::Psnr,
+        )
+        .unwrap();
+        assert!(psnr10.all < psnr90.all);
+    }
+
+    #[test]
+    #[cfg(feature = "0_6")]
+    #[allow(non_snake_case)]
+    fn test_WebPPlaneDistortion() {
+        let (width, height, buf) = WebPDecodeRGB(&lena()).unwrap();
+        let (_, psnr) = WebPPlaneDistortion(
+            &buf,
+            width as usize * 3,
+            &buf,
+            width as usize * 3,
+            width,
+            height,
+            3,
+            WebPDistortionMetric::Psnr,
+        )
+        .unwrap();
+        assert_eq!(psnr, 99.0);
+        let mut other = buf.to_vec();
+        other[0] ^= 0xFF;
+        let (distortion, psnr) = WebPPlaneDistortion(
+            &buf,
+            width as usize * 3,
+            &other,
+            width as usize * 3,
+            width,
+            height,
+            3,
+            WebPDistortionMetric::Psnr,
+        )
+        .unwrap();
+        assert!(distortion > 0.0);
+        assert!(psnr < 99.0);
+    }
+
+    #[test]
+    fn test_picture_transparency_argb() {
+        let mut picture = WebPPicture::new_argb(16, 8).unwrap();
+        for (i, px) in picture.argb_mut().unwrap().iter_mut().enumerate() {
+            let alpha = if i % 16 < 8 { 0 } else { 0x80 };
+            *px = alpha << 24 | (i as u32 * 0x010203);
+        }
+        assert!(picture.has_transparency());
+
+        picture.cleanup_transparent_area();
+        let argb = picture.argb().unwrap();
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(argb[y * 16 + x], 0);
+            }
+            for x in 8..16 {
+                assert_eq!(
+                    argb[y * 16 + x],
+                    0x80000000 | ((y * 16 + x) as u32 * 0x010203)
+                );
+            }
+        }
+
+        picture.blend_alpha(0x0000FF);
+        assert!(!picture.has_transparency());
+        let argb = picture.argb().unwrap();
+        assert_eq!(argb[0], 0xFF0000FF);
+        assert!(argb.iter().all(|&px| px >> 24 == 0xFF));
+    }
+
+    #[test]
+    fn test_picture_transparency_yuva() {
+        let mut picture = WebPPicture::new_yuva(16, 16, true).unwrap();
+        for x in picture.y_mut().unwrap() {
+            *x = 100;
+        }
+        for x in picture.u_mut().unwrap() {
+            *x = 128;
+        }
+        for x in picture.v_mut().unwrap() {
+            *x = 128;
+        }
+        for x in picture.a_mut().unwrap() {
+            *x = 0xFF;
+        }
+        assert!(!picture.has_transparency());
+        picture.a_mut().unwrap()[0] = 0;
+        assert!(picture.has_transparency());
+        picture.blend_alpha(0xFFFFFF);
+        assert!(!picture.has_transparency());
+        assert!(picture.y().unwrap()[0] > 100);
+    }
+
+    #[test]
+    fn test_picture_yuva_to_argb() {
+        let mut picture = lena_argb_picture();
+        picture.argb_to_yuva(WebPEncCSP::WEBP_YUV420).unwrap();
+        let error = argb_roundtrip_error(&mut picture);
+        assert!(picture.use_argb());
+        assert!(error < 128 * 128 * 16, "error = {}", error);
+
+        let mut picture = WebPPicture::new_argb(4, 4).unwrap();
+        assert_eq!(
+            picture.yuva_to_argb().unwrap_err(),
+            WebPEncodingError::VP8_ENC_ERROR_NULL_PARAMETER
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "u plane too small")]
+    fn test_from_yuva_small_plane() {
+        let mut y = vec![0; 16];
+        let mut u = vec![0; 3];
+        let mut v = vec![0; 4];
+        WebPPicture::from_yuva(4, 4, &mut y, 4, &mut u, &mut v, 2, None);
     }
 }
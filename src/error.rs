@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+use crate::decode::VP8StatusCode;
+
 /// An error with no information.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WebPSimpleError;
@@ -13,3 +15,143 @@ impl fmt::Display for WebPSimpleError {
 }
 
 impl std::error::Error for WebPSimpleError {}
+
+/// The kind of limit exceeded, see [`LimitError`].
+///
+/// [`LimitError`]: struct.LimitError.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LimitKind {
+    Width,
+    Height,
+    Pixels,
+    OutputBytes,
+}
+
+/// An image exceeding one of the [`Limits`].
+///
+/// [`Limits`]: ../struct.Limits.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LimitError {
+    pub kind: LimitKind,
+    /// The configured maximum.
+    pub limit: u64,
+    /// The value found in the image header.
+    pub actual: u64,
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self.kind {
+            LimitKind::Width => "width",
+            LimitKind::Height => "height",
+            LimitKind::Pixels => "pixel count",
+            LimitKind::OutputBytes => "output size",
+        };
+        write!(
+            f,
+            "image {} {} exceeds the limit of {}",
+            what, self.actual, self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+/// An error from decoding with [`Limits`].
+///
+/// [`Limits`]: ../struct.Limits.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebPDecodeError {
+    /// The data is not a valid WebP image.
+    Invalid,
+    /// libwebp reported an error status.
+    Status(VP8StatusCode),
+    /// The image exceeds a limit. Nothing was allocated for the output.
+    LimitExceeded(LimitError),
+}
+
+impl fmt::Display for WebPDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebPDecodeError::Invalid => fmt::Display::fmt(&WebPSimpleError, f),
+            WebPDecodeError::Status(status) => fmt::Display::fmt(status, f),
+            WebPDecodeError::LimitExceeded(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl std::error::Error for WebPDecodeError {}
+
+impl From<WebPSimpleError> for WebPDecodeError {
+    fn from(_: WebPSimpleError) -> Self {
+        WebPDecodeError::Invalid
+    }
+}
+
+impl From<VP8StatusCode> for WebPDecodeError {
+    fn from(status: VP8StatusCode) -> Self {
+        WebPDecodeError::Status(status)
+    }
+}
+
+impl From<LimitError> for WebPDecodeError {
+    fn from(e: LimitError) -> Self {
+        WebPDecodeError::LimitExceeded(e)
+    }
+}
+
+/// The kind of a [`RiffError`].
+///
+/// [`RiffError`]: struct.RiffError.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiffErrorKind {
+    /// The data ends in the middle of a header or chunk.
+    Truncated,
+    /// The data doesn't start with `RIFF`.
+    NotRiff,
+    /// The RIFF form type is not `WEBP`.
+    NotWebP,
+    /// The RIFF size is too small or odd.
+    InvalidRiffSize,
+    /// A chunk extends past the end of its container.
+    ChunkTooLarge([u8; 4]),
+    /// An odd-sized chunk is not followed by a padding byte.
+    MissingPadding([u8; 4]),
+    /// A chunk is too small for its fixed-size header.
+    ChunkTooSmall([u8; 4]),
+    /// The first chunk is not `VP8 `, `VP8L` or `VP8X`.
+    UnexpectedFirstChunk([u8; 4]),
+}
+
+/// A malformed RIFF container, see [`riff`].
+///
+/// [`riff`]: ../riff/index.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RiffError {
+    pub kind: RiffErrorKind,
+    /// Byte offset of the offending header or chunk.
+    pub offset: usize,
+}
+
+impl fmt::Display for RiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fourcc = |fourcc: &[u8; 4]| String::from_utf8_lossy(fourcc).into_owned();
+        match &self.kind {
+            RiffErrorKind::Truncated => f.write_str("unexpected end of data")?,
+            RiffErrorKind::NotRiff => f.write_str("not a RIFF file")?,
+            RiffErrorKind::NotWebP => f.write_str("not a WebP file")?,
+            RiffErrorKind::InvalidRiffSize => f.write_str("invalid RIFF size")?,
+            RiffErrorKind::ChunkTooLarge(c) => write!(f, "chunk {:?} too large", fourcc(c))?,
+            RiffErrorKind::MissingPadding(c) => {
+                write!(f, "missing padding after chunk {:?}", fourcc(c))?
+            }
+            RiffErrorKind::ChunkTooSmall(c) => write!(f, "chunk {:?} too small", fourcc(c))?,
+            RiffErrorKind::UnexpectedFirstChunk(c) => {
+                write!(f, "unexpected first chunk {:?}", fourcc(c))?
+            }
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for RiffError {}
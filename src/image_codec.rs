@@ -0,0 +1,353 @@
+//! [`image`] crate integration.
+//!
+//! [`image`]: https://docs.rs/image/0.25
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use image::{DynamicImage, ImageEncoder};
+//! use libwebp::image_codec::{WebPImageDecoder, WebPImageEncoder};
+//!
+//! let data: &[u8] = include_bytes!("lena.webp");
+//! let decoder = WebPImageDecoder::new(data).unwrap();
+//! let image = DynamicImage::from_decoder(decoder).unwrap();
+//!
+//! let mut output = Vec::new();
+//! WebPImageEncoder::new_lossless(&mut output)
+//!     .write_image(
+//!         image.as_bytes(),
+//!         image.width(),
+//!         image.height(),
+//!         image.color().into(),
+//!     )
+//!     .unwrap();
+//! ```
+
+use libwebp_sys as sys;
+use std::io::{Read, Write};
+
+use image::error::{
+    DecodingError, EncodingError, ImageFormatHint, LimitError, LimitErrorKind, UnsupportedError,
+    UnsupportedErrorKind,
+};
+use image::{ColorType, ExtendedColorType, ImageDecoder, ImageError, ImageFormat, ImageResult};
+
+use crate::decode::*;
+use crate::encode::*;
+
+fn decoding_error<E>(e: E) -> ImageError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    ImageError::Decoding(DecodingError::new(ImageFormat::WebP.into(), e))
+}
+
+fn encoding_error<E>(e: E) -> ImageError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    ImageError::Encoding(EncodingError::new(ImageFormat::WebP.into(), e))
+}
+
+fn unsupported_error(kind: UnsupportedErrorKind) -> ImageError {
+    ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+        ImageFormatHint::Exact(ImageFormat::WebP),
+        kind,
+    ))
+}
+
+/// An [`ImageDecoder`] for still WebP images.
+///
+/// Images with transparency are decoded as [`ColorType::Rgba8`], and others
+/// as [`ColorType::Rgb8`].
+///
+/// [`ImageDecoder`]: https://docs.rs/image/0.25/image/trait.ImageDecoder.html
+/// [`ColorType::Rgba8`]: https://docs.rs/image/0.25/image/enum.ColorType.html#variant.Rgba8
+/// [`ColorType::Rgb8`]: https://docs.rs/image/0.25/image/enum.ColorType.html#variant.Rgb8
+#[derive(Debug)]
+pub struct WebPImageDecoder {
+    data: Vec<u8>,
+    features: WebPBitstreamFeatures,
+}
+
+impl WebPImageDecoder {
+    /// Reads the whole input from `reader` and parses the header.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if reading failed, the header is invalid, or the image
+    /// is animated.
+    pub fn new<R: Read>(mut reader: R) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::from_vec(data)
+    }
+
+    /// Parses the header of the WebP image in `data`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the header is invalid, or the image is animated.
+    pub fn from_vec(data: Vec<u8>) -> ImageResult<Self> {
+        let features = WebPGetFeatures(&data).map_err(decoding_error)?;
+        if features.has_animation {
+            return Err(unsupported_error(UnsupportedErrorKind::GenericFeature(
+                "animated WebP".to_owned(),
+            )));
+        }
+        Ok(Self { data, features })
+    }
+
+    /// Returns the features parsed from the header.
+    pub fn features(&self) -> &WebPBitstreamFeatures {
+        &self.features
+    }
+}
+
+impl ImageDecoder for WebPImageDecoder {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.features.width, self.features.height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        if self.features.has_alpha {
+            ColorType::Rgba8
+        } else {
+            ColorType::Rgb8
+        }
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        assert_eq!(buf.len() as u64, self.total_bytes());
+        let width = self.features.width;
+        let result = if self.features.has_alpha {
+            WebPDecodeRGBAInto(&self.data, buf, width * 4)
+        } else {
+            WebPDecodeRGBInto(&self.data, buf, width * 3)
+        };
+        result.map_err(decoding_error)
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+/// An [`ImageEncoder`] producing still WebP images.
+///
+/// Accepts [`ExtendedColorType::Rgb8`] and [`ExtendedColorType::Rgba8`].
+///
+/// [`ImageEncoder`]: https://docs.rs/image/0.25/image/trait.ImageEncoder.html
+/// [`ExtendedColorType::Rgb8`]: https://docs.rs/image/0.25/image/enum.ExtendedColorType.html#variant.Rgb8
+/// [`ExtendedColorType::Rgba8`]: https://docs.rs/image/0.25/image/enum.ExtendedColorType.html#variant.Rgba8
+#[derive(Debug)]
+pub struct WebPImageEncoder<W: Write> {
+    writer: W,
+    lossless: bool,
+    quality: f32,
+}
+
+impl<W: Write> WebPImageEncoder<W> {
+    /// Creates an encoder with lossy compression. `quality` ranges from 0
+    /// (small output, low quality) to 100 (large output, high quality).
+    pub fn new_lossy(writer: W, quality: f32) -> Self {
+        Self {
+            writer,
+            lossless: false,
+            quality,
+        }
+    }
+
+    /// Creates an encoder with lossless compression.
+    pub fn new_lossless(writer: W) -> Self {
+        Self {
+            writer,
+            lossless: true,
+            quality: 75.0,
+        }
+    }
+
+    pub fn lossless(&self) -> bool {
+        self.lossless
+    }
+
+    /// Quality factor for lossy compression.
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+}
+
+impl<W: Write> image::ImageEncoder for WebPImageEncoder<W> {
+    fn write_image(
+        mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ExtendedColorType,
+    ) -> ImageResult<()> {
+        let bytes_per_pixel = match color_type {
+            ExtendedColorType::Rgb8 => 3,
+            ExtendedColorType::Rgba8 => 4,
+            _ => return Err(unsupported_error(UnsupportedErrorKind::Color(color_type))),
+        };
+        let max_dimension = sys::WEBP_MAX_DIMENSION as u32;
+        if width > max_dimension || height > max_dimension {
+            return Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::DimensionError,
+            )));
+        }
+        let expected_len = u64::from(width) * u64::from(height) * bytes_per_pixel;
+        assert_eq!(buf.len() as u64, expected_len);
+        let stride = width * bytes_per_pixel as u32;
+        let result = match (color_type, self.lossless) {
+            (ExtendedColorType::Rgb8, false) => {
+                WebPEncodeRGB(buf, width, height, stride, self.quality)
+            }
+            (ExtendedColorType::Rgb8, true) => WebPEncodeLosslessRGB(buf, width, height, stride),
+            (_, false) => WebPEncodeRGBA(buf, width, height, stride, self.quality),
+            (_, true) => WebPEncodeLosslessRGBA(buf, width, height, stride),
+        };
+        let data = result.map_err(encoding_error)?;
+        self.writer.write_all(&data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{DynamicImage, ImageEncoder};
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    fn encode(image: &DynamicImage, encoder: WebPImageEncoder<&mut Vec<u8>>) {
+        encoder
+            .write_image(
+                image.as_bytes(),
+                image.width(),
+                image.height(),
+                image.color().into(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoder = WebPImageDecoder::new(&lena()[..]).unwrap();
+        assert_eq!(decoder.dimensions(), (128, 128));
+        assert_eq!(decoder.color_type(), ColorType::Rgb8);
+        let image = DynamicImage::from_decoder(decoder).unwrap();
+        let (_, _, expected) = WebPDecodeRGB(&lena()).unwrap();
+        assert_eq!(image.as_bytes(), &expected[..]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(WebPImageDecoder::new(&b"RIFF\0\0\0\0WEBP"[..]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_lossless() {
+        let image = DynamicImage::from_decoder(WebPImageDecoder::new(&lena()[..]).unwrap())
+            .unwrap()
+            .into_rgba8();
+        let image = DynamicImage::ImageRgba8(image);
+
+        let mut output = Vec::new();
+        encode(&image, WebPImageEncoder::new_lossless(&mut output));
+        let decoder = WebPImageDecoder::from_vec(output).unwrap();
+        assert_eq!(decoder.features().format, WebPBitstreamFormat::Lossless);
+        let decoded = DynamicImage::from_decoder(decoder).unwrap();
+        // Fully opaque, hence decoded as RGB.
+        assert_eq!(decoded.color(), ColorType::Rgb8);
+        assert_eq!(decoded.to_rgba8(), image.to_rgba8());
+    }
+
+    #[test]
+    fn test_roundtrip_lossy() {
+        let image =
+            DynamicImage::from_decoder(WebPImageDecoder::new(&lena()[..]).unwrap()).unwrap();
+        let mut small = Vec::new();
+        encode(&image, WebPImageEncoder::new_lossy(&mut small, 10.0));
+        let mut large = Vec::new();
+        encode(&image, WebPImageEncoder::new_lossy(&mut large, 95.0));
+        assert!(small.len() < large.len());
+
+        let decoder = WebPImageDecoder::from_vec(large).unwrap();
+        assert_eq!(decoder.features().format, WebPBitstreamFormat::Lossy);
+        let decoded = DynamicImage::from_decoder(decoder).unwrap();
+        assert_eq!(decoded.color(), ColorType::Rgb8);
+        let diff = decoded
+            .as_bytes()
+            .iter()
+            .zip(image.as_bytes())
+            .map(|(&x, &y)| (i32::from(x) - i32::from(y)).abs())
+            .sum::<i32>();
+        assert!(diff / (image.as_bytes().len() as i32) < 4);
+    }
+
+    #[test]
+    fn test_roundtrip_alpha() {
+        let rgba = (0..16 * 16)
+            .flat_map(|i| vec![i as u8, 0, 255 - i as u8, (i % 7) as u8 * 40])
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+        WebPImageEncoder::new_lossless(&mut output)
+            .write_image(&rgba, 16, 16, ExtendedColorType::Rgba8)
+            .unwrap();
+        let decoder = WebPImageDecoder::from_vec(output).unwrap();
+        assert_eq!(decoder.color_type(), ColorType::Rgba8);
+        let decoded = DynamicImage::from_decoder(decoder).unwrap();
+        for (px, expected) in decoded.as_bytes().chunks(4).zip(rgba.chunks(4)) {
+            assert_eq!(px[3], expected[3]);
+            if expected[3] != 0 {
+                assert_eq!(px, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_unsupported_color() {
+        let result = WebPImageEncoder::new_lossless(Vec::new()).write_image(
+            &[0; 4],
+            2,
+            2,
+            ExtendedColorType::L8,
+        );
+        match result {
+            Err(ImageError::Unsupported(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode_too_large() {
+        let result = WebPImageEncoder::new_lossless(Vec::new()).write_image(
+            &[],
+            u32::max_value(),
+            0,
+            ExtendedColorType::Rgba8,
+        );
+        match result {
+            Err(ImageError::Limits(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode_unsupported_color_sub_byte() {
+        let result = WebPImageEncoder::new_lossless(Vec::new()).write_image(
+            &[0; 1],
+            2,
+            2,
+            ExtendedColorType::L1,
+        );
+        match result {
+            Err(ImageError::Unsupported(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -66,9 +66,32 @@
 //! ```
 
 pub use crate::decode::*;
+#[cfg(feature = "demux")]
+pub use crate::demux::*;
 pub use crate::encode::*;
+pub use crate::limits::*;
+#[cfg(feature = "mux")]
+pub use crate::mux::*;
+#[cfg(any(feature = "mux", feature = "demux"))]
+pub use crate::mux_types::*;
+pub use crate::version::*;
 
 pub mod boxed;
 mod decode;
+#[cfg(feature = "demux")]
+mod demux;
 mod encode;
 pub mod error;
+#[cfg(feature = "image")]
+pub mod image_codec;
+mod limits;
+#[cfg(feature = "mux")]
+mod mux;
+#[cfg(any(feature = "mux", feature = "demux"))]
+mod mux_types;
+#[cfg(feature = "pixel")]
+pub mod pixel;
+pub mod riff;
+#[cfg(test)]
+mod test_utils;
+mod version;
@@ -0,0 +1,630 @@
+use std::pin::Pin;
+
+use crate::boxed::{WebpBox, WebpYuvaImage};
+use crate::decode::*;
+#[cfg(feature = "demux")]
+use crate::demux::{WebPDemux, WebPDemuxer};
+use crate::error::{LimitError, LimitKind, WebPDecodeError};
+
+/// Upper bounds on the images to decode, protecting against decompression
+/// bombs.
+///
+/// The limits are checked against the header, before any allocation for
+/// the output. A `None` field means no limit, which is the default.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::error::{LimitKind, WebPDecodeError};
+/// use libwebp::Limits;
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let limits = Limits {
+///     max_pixels: Some(64 * 64),
+///     ..Limits::default()
+/// };
+/// match limits.decode_rgba(data) {
+///     Err(WebPDecodeError::LimitExceeded(e)) => assert_eq!(e.kind, LimitKind::Pixels),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// Maximum of `width * height`.
+    pub max_pixels: Option<u64>,
+    /// Maximum size of the decoded output, in bytes.
+    pub max_output_bytes: Option<u64>,
+}
+
+// Size of the output buffer libwebp allocates for the given mode.
+fn output_bytes(mode: WEBP_CSP_MODE, width: u32, height: u32) -> u64 {
+    use crate::decode::WEBP_CSP_MODE::*;
+
+    let pixels = u64::from(width) * u64::from(height);
+    let uv_pixels = u64::from((width + 1) / 2) * u64::from((height + 1) / 2);
+    match mode {
+        MODE_RGB | MODE_BGR => pixels * 3,
+        MODE_RGBA | MODE_BGRA | MODE_ARGB | MODE_rgbA | MODE_bgrA | MODE_Argb => pixels * 4,
+        MODE_RGBA_4444 | MODE_rgbA_4444 | MODE_RGB_565 => pixels * 2,
+        MODE_YUV => pixels + uv_pixels * 2,
+        MODE_YUVA => pixels * 2 + uv_pixels * 2,
+    }
+}
+
+fn check_limit(kind: LimitKind, limit: Option<u64>, actual: u64) -> Result<(), LimitError> {
+    match limit {
+        Some(limit) if actual > limit => Err(LimitError {
+            kind,
+            limit,
+            actual,
+        }),
+        _ => Ok(()),
+    }
+}
+
+impl Limits {
+    /// Checks the dimensions of an image to be decoded in `mode`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` describing the first limit exceeded.
+    pub fn check(&self, width: u32, height: u32, mode: WEBP_CSP_MODE) -> Result<(), LimitError> {
+        check_limit(
+            LimitKind::Width,
+            self.max_width.map(u64::from),
+            u64::from(width),
+        )?;
+        check_limit(
+            LimitKind::Height,
+            self.max_height.map(u64::from),
+            u64::from(height),
+        )?;
+        check_limit(
+            LimitKind::Pixels,
+            self.max_pixels,
+            u64::from(width) * u64::from(height),
+        )?;
+        check_limit(
+            LimitKind::OutputBytes,
+            self.max_output_bytes,
+            output_bytes(mode, width, height),
+        )
+    }
+
+    /// Parses the header in `data` and checks it against the limits.
+    ///
+    /// For animations, the canvas size is checked. Use
+    /// [`check_demuxer`](#method.check_demuxer) to check the frames as well.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the header is invalid or incomplete, or exceeds a
+    /// limit.
+    pub fn check_data(
+        &self,
+        data: &[u8],
+        mode: WEBP_CSP_MODE,
+    ) -> Result<WebPBitstreamFeatures, WebPDecodeError> {
+        let features = WebPGetFeatures(data)?;
+        self.check(features.width, features.height, mode)?;
+        Ok(features)
+    }
+
+    fn decode_with<F>(
+        &self,
+        data: &[u8],
+        mode: WEBP_CSP_MODE,
+        decode: F,
+    ) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError>
+    where
+        F: FnOnce(&[u8]) -> Result<(u32, u32, WebpBox<[u8]>), crate::error::WebPSimpleError>,
+    {
+        self.check_data(data, mode)?;
+        Ok(decode(data)?)
+    }
+
+    /// Same as [`WebPDecodeRGBA`], but checks the limits first.
+    ///
+    /// [`WebPDecodeRGBA`]: fn.WebPDecodeRGBA.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_rgba(&self, data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError> {
+        self.decode_with(data, WEBP_CSP_MODE::MODE_RGBA, WebPDecodeRGBA)
+    }
+
+    /// Same as [`WebPDecodeARGB`], but checks the limits first.
+    ///
+    /// [`WebPDecodeARGB`]: fn.WebPDecodeARGB.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_argb(&self, data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError> {
+        self.decode_with(data, WEBP_CSP_MODE::MODE_ARGB, WebPDecodeARGB)
+    }
+
+    /// Same as [`WebPDecodeBGRA`], but checks the limits first.
+    ///
+    /// [`WebPDecodeBGRA`]: fn.WebPDecodeBGRA.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_bgra(&self, data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError> {
+        self.decode_with(data, WEBP_CSP_MODE::MODE_BGRA, WebPDecodeBGRA)
+    }
+
+    /// Same as [`WebPDecodeRGB`], but checks the limits first.
+    ///
+    /// [`WebPDecodeRGB`]: fn.WebPDecodeRGB.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_rgb(&self, data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError> {
+        self.decode_with(data, WEBP_CSP_MODE::MODE_RGB, WebPDecodeRGB)
+    }
+
+    /// Same as [`WebPDecodeBGR`], but checks the limits first.
+    ///
+    /// [`WebPDecodeBGR`]: fn.WebPDecodeBGR.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_bgr(&self, data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPDecodeError> {
+        self.decode_with(data, WEBP_CSP_MODE::MODE_BGR, WebPDecodeBGR)
+    }
+
+    /// Same as [`WebPDecodeYUVA`], but checks the limits first.
+    ///
+    /// [`WebPDecodeYUVA`]: fn.WebPDecodeYUVA.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    pub fn decode_yuva(&self, data: &[u8]) -> Result<WebpYuvaImage, WebPDecodeError> {
+        let features = WebPGetFeatures(data)?;
+        let mode = if features.has_alpha {
+            WEBP_CSP_MODE::MODE_YUVA
+        } else {
+            WEBP_CSP_MODE::MODE_YUV
+        };
+        self.check(features.width, features.height, mode)?;
+        Ok(WebPDecodeYUVA(data)?)
+    }
+}
+
+#[cfg(feature = "demux")]
+impl Limits {
+    /// Checks the canvas and every frame of `demuxer`, to be decoded in
+    /// `mode`.
+    ///
+    /// libwebp rejects frames which don't fit in the canvas, but each frame
+    /// is checked too, as frames are decoded into buffers of their own.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` describing the first limit exceeded.
+    pub fn check_demuxer(
+        &self,
+        demuxer: &WebPDemuxer,
+        mode: WEBP_CSP_MODE,
+    ) -> Result<(), LimitError> {
+        self.check(demuxer.canvas_width(), demuxer.canvas_height(), mode)?;
+        for frame in demuxer.frames() {
+            self.check(frame.width, frame.height, mode)?;
+        }
+        Ok(())
+    }
+
+    /// Same as [`WebPDemux`], but checks the limits with
+    /// [`check_demuxer`](#method.check_demuxer) before returning the
+    /// demuxer.
+    ///
+    /// [`WebPDemux`]: fn.WebPDemux.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is invalid or exceeds a limit.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{Limits, WebPDecodeRGBA, WEBP_CSP_MODE};
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let limits = Limits {
+    ///     max_pixels: Some(1 << 20),
+    ///     ..Limits::default()
+    /// };
+    /// let demuxer = limits.demux(data, WEBP_CSP_MODE::MODE_RGBA).unwrap();
+    /// for frame in demuxer.frames() {
+    ///     let (_, _, rgba) = WebPDecodeRGBA(frame.fragment).unwrap();
+    /// #   assert_eq!(rgba.len(), 128 * 128 * 4);
+    /// }
+    /// ```
+    pub fn demux<'a>(
+        &self,
+        data: &'a [u8],
+        mode: WEBP_CSP_MODE,
+    ) -> Result<WebPDemuxer<'a>, WebPDecodeError> {
+        let demuxer = WebPDemux(data)?;
+        self.check_demuxer(&demuxer, mode)?;
+        Ok(demuxer)
+    }
+}
+
+/// An incremental decoder which checks [`Limits`] before libwebp allocates
+/// the output.
+///
+/// Input is held back until the header is complete, then checked and handed
+/// over to the underlying [`WebPIDecoder`].
+///
+/// [`Limits`]: struct.Limits.html
+/// [`WebPIDecoder`]: struct.WebPIDecoder.html
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{LimitedIDecoder, Limits, VP8StatusCode, WebPIDecGetRGB, WEBP_CSP_MODE};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let limits = Limits {
+///     max_output_bytes: Some(1 << 20),
+///     ..Limits::default()
+/// };
+/// let mut idec = LimitedIDecoder::new_rgb(WEBP_CSP_MODE::MODE_RGBA, limits);
+/// for chunk in data.chunks(100) {
+///     if idec.append(chunk).unwrap() == VP8StatusCode::VP8_STATUS_OK {
+///         break;
+///     }
+/// }
+/// let result = WebPIDecGetRGB(idec.decoder()).unwrap();
+/// assert_eq!(result.last_y, 128);
+/// ```
+#[derive(Debug)]
+pub struct LimitedIDecoder {
+    idec: WebPIDecoderBox,
+    mode: WEBP_CSP_MODE,
+    limits: Limits,
+    // Input held back until the header is checked.
+    header: Option<Vec<u8>>,
+}
+
+impl LimitedIDecoder {
+    /// Same as [`WebPINewRGB`], with limits.
+    ///
+    /// [`WebPINewRGB`]: fn.WebPINewRGB.html
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `csp` is not an RGB mode.
+    pub fn new_rgb(csp: WEBP_CSP_MODE, limits: Limits) -> Self {
+        Self {
+            idec: WebPINewRGB(csp),
+            mode: csp,
+            limits,
+            header: Some(Vec::new()),
+        }
+    }
+
+    /// Same as [`WebPINewYUVA`], with limits.
+    ///
+    /// [`WebPINewYUVA`]: fn.WebPINewYUVA.html
+    pub fn new_yuva(limits: Limits) -> Self {
+        Self {
+            idec: WebPINewYUVA(),
+            mode: WEBP_CSP_MODE::MODE_YUVA,
+            limits,
+            header: Some(Vec::new()),
+        }
+    }
+
+    /// Appends `data` and decodes as far as possible, like [`WebPIAppend`].
+    ///
+    /// Returns `VP8_STATUS_OK` when the image is complete, and
+    /// `VP8_STATUS_SUSPENDED` when more data is needed.
+    ///
+    /// [`WebPIAppend`]: fn.WebPIAppend.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` with the status code if decoding failed, or
+    /// `LimitExceeded` once the header shows that the image is too large.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `data` is empty.
+    pub fn append(&mut self, data: &[u8]) -> Result<VP8StatusCode, WebPDecodeError> {
+        assert!(!data.is_empty(), "LimitedIDecoder::append: empty data");
+        let status = if let Some(mut header) = self.header.take() {
+            header.extend_from_slice(data);
+            match self.limits.check_data(&header, self.mode) {
+                Ok(_) => WebPIAppend(self.idec.as_mut(), &header),
+                Err(WebPDecodeError::Status(VP8StatusCode::VP8_STATUS_NOT_ENOUGH_DATA)) => {
+                    self.header = Some(header);
+                    return Ok(VP8StatusCode::VP8_STATUS_SUSPENDED);
+                }
+                Err(e) => {
+                    self.header = Some(header);
+                    return Err(e);
+                }
+            }
+        } else {
+            WebPIAppend(self.idec.as_mut(), data)
+        };
+        match status {
+            VP8StatusCode::VP8_STATUS_OK | VP8StatusCode::VP8_STATUS_SUSPENDED => Ok(status),
+            _ => Err(WebPDecodeError::Status(status)),
+        }
+    }
+
+    /// The underlying decoder, e.g. for [`WebPIDecGetRGB`].
+    ///
+    /// [`WebPIDecGetRGB`]: fn.WebPIDecGetRGB.html
+    pub fn decoder(&self) -> Pin<&WebPIDecoder> {
+        self.idec.as_ref()
+    }
+
+    /// Turns into the underlying decoder.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the header hasn't been checked yet.
+    pub fn into_decoder(self) -> WebPIDecoderBox {
+        assert!(self.header.is_none(), "header not checked yet");
+        self.idec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    // A lossless header claiming the given dimensions, without image data.
+    fn bomb(width: u32, height: u32) -> Vec<u8> {
+        let bits = (width - 1) | (height - 1) << 14;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"RIFF");
+        data.extend_from_slice(&18u32.to_le_bytes());
+        data.extend_from_slice(b"WEBPVP8L");
+        data.extend_from_slice(&6u32.to_le_bytes());
+        data.push(0x2f);
+        data.extend_from_slice(&bits.to_le_bytes());
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn test_check() {
+        let limits = Limits {
+            max_width: Some(100),
+            max_height: Some(200),
+            max_pixels: Some(10_000),
+            max_output_bytes: Some(30_000),
+        };
+        assert_eq!(limits.check(100, 100, WEBP_CSP_MODE::MODE_RGB), Ok(()));
+        assert_eq!(
+            limits.check(101, 1, WEBP_CSP_MODE::MODE_RGB),
+            Err(LimitError {
+                kind: LimitKind::Width,
+                limit: 100,
+                actual: 101,
+            })
+        );
+        assert_eq!(
+            limits
+                .check(1, 201, WEBP_CSP_MODE::MODE_RGB)
+                .unwrap_err()
+                .kind,
+            LimitKind::Height
+        );
+        assert_eq!(
+            limits
+                .check(100, 101, WEBP_CSP_MODE::MODE_RGB)
+                .unwrap_err()
+                .kind,
+            LimitKind::Pixels
+        );
+        assert_eq!(
+            limits.check(100, 100, WEBP_CSP_MODE::MODE_RGBA),
+            Err(LimitError {
+                kind: LimitKind::OutputBytes,
+                limit: 30_000,
+                actual: 40_000,
+            })
+        );
+        assert_eq!(limits.check(100, 100, WEBP_CSP_MODE::MODE_YUVA), Ok(()));
+        assert_eq!(
+            Limits::default().check(16383, 16383, WEBP_CSP_MODE::MODE_RGBA),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_decode_bomb() {
+        let data = bomb(16383, 16383);
+        let features = WebPGetFeatures(&data).unwrap();
+        assert_eq!((features.width, features.height), (16383, 16383));
+
+        let limits = Limits {
+            max_output_bytes: Some(1 << 28),
+            ..Limits::default()
+        };
+        let expected = WebPDecodeError::LimitExceeded(LimitError {
+            kind: LimitKind::OutputBytes,
+            limit: 1 << 28,
+            actual: 16383 * 16383 * 4,
+        });
+        assert_eq!(limits.decode_rgba(&data).unwrap_err(), expected);
+        assert!(limits.decode_yuva(&data).is_err());
+
+        let mut idec = LimitedIDecoder::new_rgb(WEBP_CSP_MODE::MODE_RGBA, limits);
+        assert_eq!(
+            idec.append(&data[..10]),
+            Ok(VP8StatusCode::VP8_STATUS_SUSPENDED)
+        );
+        assert_eq!(idec.append(&data[10..]), Err(expected));
+    }
+
+    #[test]
+    fn test_decode_within_limits() {
+        let limits = Limits {
+            max_width: Some(128),
+            max_height: Some(128),
+            max_pixels: Some(128 * 128),
+            max_output_bytes: Some(128 * 128 * 4),
+        };
+        let (width, height, buf) = limits.decode_rgba(&lena()).unwrap();
+        assert_eq!((width, height), (128, 128));
+        assert_eq!(&buf[..], &WebPDecodeRGBA(&lena()).unwrap().2[..]);
+        assert!(limits.decode_rgb(&lena()).is_ok());
+        assert!(limits.decode_yuva(&lena()).is_ok());
+        assert_eq!(
+            limits.decode_rgba(b"RIFF"),
+            Err(WebPDecodeError::Status(
+                VP8StatusCode::VP8_STATUS_NOT_ENOUGH_DATA
+            ))
+        );
+
+        let limits = Limits {
+            max_width: Some(127),
+            ..Limits::default()
+        };
+        assert!(limits.decode_bgr(&lena()).is_err());
+    }
+
+    #[test]
+    fn test_incremental() {
+        let data = lena();
+        let limits = Limits {
+            max_pixels: Some(128 * 128),
+            ..Limits::default()
+        };
+        for &chunk_len in &[1, 7, 64, data.len()] {
+            let mut idec = LimitedIDecoder::new_yuva(limits);
+            let mut status = VP8StatusCode::VP8_STATUS_SUSPENDED;
+            for chunk in data.chunks(chunk_len) {
+                status = idec.append(chunk).unwrap();
+            }
+            assert_eq!(status, VP8StatusCode::VP8_STATUS_OK);
+            let result = WebPIDecGetYUVA(idec.decoder()).unwrap();
+            assert_eq!(
+                (result.width, result.height, result.last_y),
+                (128, 128, 128)
+            );
+        }
+
+        let limits = Limits {
+            max_pixels: Some(128 * 127),
+            ..Limits::default()
+        };
+        let mut idec = LimitedIDecoder::new_rgb(WEBP_CSP_MODE::MODE_RGB, limits);
+        match idec.append(&data) {
+            Err(WebPDecodeError::LimitExceeded(e)) => assert_eq!(e.kind, LimitKind::Pixels),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "demux")]
+    fn test_demux() {
+        use crate::test_utils::animation;
+
+        let data = animation();
+        let limits = Limits {
+            max_pixels: Some(8 * 4),
+            ..Limits::default()
+        };
+        let demuxer = limits.demux(&data, WEBP_CSP_MODE::MODE_RGBA).unwrap();
+        assert_eq!(demuxer.frame_count(), 3);
+
+        let limits = Limits {
+            max_width: Some(7),
+            ..Limits::default()
+        };
+        assert_eq!(
+            limits.demux(&data, WEBP_CSP_MODE::MODE_RGBA).unwrap_err(),
+            WebPDecodeError::LimitExceeded(LimitError {
+                kind: LimitKind::Width,
+                limit: 7,
+                actual: 8,
+            })
+        );
+        let limits = Limits {
+            max_width: Some(4),
+            ..Limits::default()
+        };
+        let demuxer = WebPDemux(&data).unwrap();
+        assert_eq!(
+            limits.check_demuxer(&demuxer, WEBP_CSP_MODE::MODE_RGBA),
+            Err(LimitError {
+                kind: LimitKind::Width,
+                limit: 4,
+                actual: 8,
+            })
+        );
+        assert!(limits
+            .demux(b"RIFF\0\0\0\0WEBP", WEBP_CSP_MODE::MODE_RGBA)
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "demux")]
+    fn test_demux_bomb() {
+        use crate::test_utils::{chunk, riff, u24};
+
+        // A single 16383x16383 frame without image data.
+        let vp8x = [&[0x02, 0, 0, 0][..], &u24(16382), &u24(16382)].concat();
+        let anmf = [
+            u24(0),
+            u24(0),
+            u24(16382),
+            u24(16382),
+            u24(100),
+            vec![0],
+            bomb(16383, 16383)[12..].to_vec(),
+        ]
+        .concat();
+        let data = riff(&[
+            chunk(b"VP8X", &vp8x),
+            chunk(b"ANIM", &[0; 6]),
+            chunk(b"ANMF", &anmf),
+        ]);
+        let demuxer = WebPDemux(&data).unwrap();
+        let frame = demuxer.frame(1).unwrap();
+        assert_eq!((frame.width, frame.height), (16383, 16383));
+
+        let limits = Limits {
+            max_output_bytes: Some(1 << 28),
+            ..Limits::default()
+        };
+        assert_eq!(
+            limits.demux(&data, WEBP_CSP_MODE::MODE_RGBA).unwrap_err(),
+            WebPDecodeError::LimitExceeded(LimitError {
+                kind: LimitKind::OutputBytes,
+                limit: 1 << 28,
+                actual: 16383 * 16383 * 4,
+            })
+        );
+        assert_eq!(
+            Limits::default().check_demuxer(&demuxer, WEBP_CSP_MODE::MODE_RGBA),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,1508 @@
+use libwebp_sys as sys;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::*;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use crate::boxed::{alloc_bytes, WebPFree, WebpBox};
+use crate::encode::{WebPConfig, WebPEncode, WebPEncodingError, WebPPicture};
+use crate::mux_types::*;
+use crate::riff;
+
+/// Error codes returned by the mux functions.
+#[allow(non_camel_case_types)]
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPMuxError {
+    WEBP_MUX_OK = 1,
+    WEBP_MUX_NOT_FOUND = 0,
+    WEBP_MUX_INVALID_ARGUMENT = -1,
+    WEBP_MUX_BAD_DATA = -2,
+    WEBP_MUX_MEMORY_ERROR = -3,
+    WEBP_MUX_NOT_ENOUGH_DATA = -4,
+}
+
+impl WebPMuxError {
+    pub fn from_raw(raw: sys::WebPMuxError) -> Self {
+        use self::WebPMuxError::*;
+
+        match raw {
+            sys::WEBP_MUX_OK => WEBP_MUX_OK,
+            sys::WEBP_MUX_NOT_FOUND => WEBP_MUX_NOT_FOUND,
+            sys::WEBP_MUX_INVALID_ARGUMENT => WEBP_MUX_INVALID_ARGUMENT,
+            sys::WEBP_MUX_BAD_DATA => WEBP_MUX_BAD_DATA,
+            sys::WEBP_MUX_MEMORY_ERROR => WEBP_MUX_MEMORY_ERROR,
+            sys::WEBP_MUX_NOT_ENOUGH_DATA => WEBP_MUX_NOT_ENOUGH_DATA,
+            _ => panic!("WebPMuxError::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPMuxError {
+        use self::WebPMuxError::*;
+
+        match self {
+            WEBP_MUX_OK => sys::WEBP_MUX_OK,
+            WEBP_MUX_NOT_FOUND => sys::WEBP_MUX_NOT_FOUND,
+            WEBP_MUX_INVALID_ARGUMENT => sys::WEBP_MUX_INVALID_ARGUMENT,
+            WEBP_MUX_BAD_DATA => sys::WEBP_MUX_BAD_DATA,
+            WEBP_MUX_MEMORY_ERROR => sys::WEBP_MUX_MEMORY_ERROR,
+            WEBP_MUX_NOT_ENOUGH_DATA => sys::WEBP_MUX_NOT_ENOUGH_DATA,
+        }
+    }
+}
+
+impl fmt::Display for WebPMuxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::WebPMuxError::*;
+
+        f.write_str(match *self {
+            WEBP_MUX_OK => "no error",
+            WEBP_MUX_NOT_FOUND => "not found",
+            WEBP_MUX_INVALID_ARGUMENT => "invalid argument",
+            WEBP_MUX_BAD_DATA => "bad data",
+            WEBP_MUX_MEMORY_ERROR => "out of memory",
+            WEBP_MUX_NOT_ENOUGH_DATA => "not enough data",
+        })
+    }
+}
+
+impl std::error::Error for WebPMuxError {}
+
+// Converts a raw status into `Result`, treating anything but
+// `WEBP_MUX_OK` as an error.
+pub(crate) fn mux_result(raw: sys::WebPMuxError) -> Result<(), WebPMuxError> {
+    match WebPMuxError::from_raw(raw) {
+        WebPMuxError::WEBP_MUX_OK => Ok(()),
+        e => Err(e),
+    }
+}
+
+/// A WebP container under construction or modification.
+///
+/// The mux either owns a copy of its chunks, or borrows them from the
+/// caller for `'a`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPMuxAssemble, WebPMuxCreate};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let mut mux = WebPMuxCreate(data).unwrap();
+/// let output = WebPMuxAssemble(&mut mux).unwrap();
+/// assert_eq!(&output[..], data);
+/// ```
+pub struct WebPMux<'a> {
+    ptr: NonNull<sys::WebPMux>,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+unsafe impl<'a> Send for WebPMux<'a> {}
+unsafe impl<'a> Sync for WebPMux<'a> {}
+
+impl<'a> Drop for WebPMux<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::WebPMuxDelete(self.ptr.as_ptr());
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WebPMux<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("WebPMux")
+    }
+}
+
+impl<'a> WebPMux<'a> {
+    /// Creates `WebPMux` from a raw pointer.
+    ///
+    /// ## Safety
+    ///
+    /// - `raw` must be a valid mux object.
+    /// - The mux must be exclusively owned by the caller.
+    /// - The data referenced by the mux must outlive `'a`.
+    /// - `raw` must be freeable via `WebPMuxDelete`.
+    pub unsafe fn from_raw(raw: NonNull<sys::WebPMux>) -> Self {
+        Self {
+            ptr: raw,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_raw(self) -> NonNull<sys::WebPMux> {
+        let ret = self.ptr;
+        mem::forget(self);
+        ret
+    }
+
+    pub fn as_ptr(&self) -> *const sys::WebPMux {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut sys::WebPMux {
+        self.ptr.as_ptr()
+    }
+}
+
+pub(crate) fn webp_data(data: &[u8]) -> sys::WebPData {
+    sys::WebPData {
+        bytes: data.as_ptr(),
+        size: data.len(),
+    }
+}
+
+/// Creates an empty mux object.
+///
+/// ## Panics
+///
+/// Panics if the allocation failed.
+#[allow(non_snake_case)]
+pub fn WebPMuxNew() -> WebPMux<'static> {
+    let result = unsafe { sys::WebPNewInternal(sys::WEBP_MUX_ABI_VERSION) };
+    if let Some(result) = NonNull::new(result) {
+        unsafe { WebPMux::from_raw(result) }
+    } else {
+        panic!("WebPMuxNew: allocation failed");
+    }
+}
+
+unsafe fn create(data: &[u8], copy_data: bool) -> Result<NonNull<sys::WebPMux>, WebPMuxError> {
+    let bitstream = webp_data(data);
+    let result =
+        sys::WebPMuxCreateInternal(&bitstream, copy_data as c_int, sys::WEBP_MUX_ABI_VERSION);
+    NonNull::new(result).ok_or(WebPMuxError::WEBP_MUX_BAD_DATA)
+}
+
+/// Creates a mux object from a WebP RIFF container, copying the data.
+///
+/// ## Errors
+///
+/// Returns `Err` if `data` is not a valid WebP container or the allocation
+/// failed. libwebp doesn't tell them apart, so both are reported as
+/// `WEBP_MUX_BAD_DATA`.
+#[allow(non_snake_case)]
+pub fn WebPMuxCreate(data: &[u8]) -> Result<WebPMux<'static>, WebPMuxError> {
+    unsafe { create(data, true).map(|raw| WebPMux::from_raw(raw)) }
+}
+
+/// Same as [`WebPMuxCreate`], but the mux borrows the chunks from `data`
+/// instead of copying them.
+///
+/// [`WebPMuxCreate`]: fn.WebPMuxCreate.html
+///
+/// ## Errors
+///
+/// Returns `Err` if `data` is not a valid WebP container or the allocation
+/// failed.
+#[allow(non_snake_case)]
+pub fn WebPMuxCreateBorrowed(data: &[u8]) -> Result<WebPMux<'_>, WebPMuxError> {
+    unsafe { create(data, false).map(|raw| WebPMux::from_raw(raw)) }
+}
+
+/// Assembles the chunks of `mux` into a WebP RIFF container.
+///
+/// The `VP8X` chunk is created or updated as needed.
+///
+/// ## Errors
+///
+/// Returns `Err` if the mux is inconsistent, e.g. has no image, or the
+/// allocation failed.
+#[allow(non_snake_case)]
+pub fn WebPMuxAssemble(mux: &mut WebPMux) -> Result<WebpBox<[u8]>, WebPMuxError> {
+    let mut assembled = sys::WebPData {
+        bytes: std::ptr::null(),
+        size: 0,
+    };
+    mux_result(unsafe { sys::WebPMuxAssemble(mux.as_mut_ptr(), &mut assembled) })?;
+    if assembled.bytes.is_null() {
+        return Err(WebPMuxError::WEBP_MUX_MEMORY_ERROR);
+    }
+    Ok(unsafe {
+        WebpBox::from_raw(slice::from_raw_parts_mut(
+            assembled.bytes as *mut u8,
+            assembled.size,
+        ))
+    })
+}
+
+/// Gets a reference to the data of the chunk with the given `fourcc`.
+///
+/// Image chunks (`VP8 `, `VP8L`, `ALPH` and `ANMF`) can't be read this way.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_NOT_FOUND` if there is no such chunk, and
+/// `WEBP_MUX_INVALID_ARGUMENT` for image chunks.
+#[allow(non_snake_case)]
+pub fn WebPMuxGetChunk<'b>(mux: &'b WebPMux, fourcc: &[u8; 4]) -> Result<&'b [u8], WebPMuxError> {
+    let mut chunk_data = webp_data(&[]);
+    mux_result(unsafe {
+        sys::WebPMuxGetChunk(
+            mux.as_ptr(),
+            fourcc.as_ptr() as *const c_char,
+            &mut chunk_data,
+        )
+    })?;
+    if chunk_data.size == 0 {
+        return Ok(&[]);
+    }
+    Ok(unsafe { slice::from_raw_parts(chunk_data.bytes, chunk_data.size) })
+}
+
+/// Adds a chunk with the given `fourcc`, replacing any existing chunks of
+/// the same kind. The data is copied.
+///
+/// Only a single chunk not interpreted by libwebp can be added this way;
+/// use [`WebPMux::insert_unknown_chunk`] for more.
+///
+/// [`WebPMux::insert_unknown_chunk`]: struct.WebPMux.html#method.insert_unknown_chunk
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_INVALID_ARGUMENT` for image chunks or oversized data,
+/// and `WEBP_MUX_MEMORY_ERROR` if the allocation failed.
+#[allow(non_snake_case)]
+pub fn WebPMuxSetChunk(
+    mux: &mut WebPMux,
+    fourcc: &[u8; 4],
+    chunk_data: &[u8],
+) -> Result<(), WebPMuxError> {
+    let chunk_data = webp_data(chunk_data);
+    mux_result(unsafe {
+        sys::WebPMuxSetChunk(
+            mux.as_mut_ptr(),
+            fourcc.as_ptr() as *const c_char,
+            &chunk_data,
+            1,
+        )
+    })
+}
+
+/// Deletes all the chunks with the given `fourcc`.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_NOT_FOUND` if there is no such chunk.
+#[allow(non_snake_case)]
+pub fn WebPMuxDeleteChunk(mux: &mut WebPMux, fourcc: &[u8; 4]) -> Result<(), WebPMuxError> {
+    mux_result(unsafe {
+        sys::WebPMuxDeleteChunk(mux.as_mut_ptr(), fourcc.as_ptr() as *const c_char)
+    })
+}
+
+/// Kinds of metadata chunks.
+///
+/// The matching `VP8X` flags are updated by [`WebPMuxAssemble`].
+///
+/// [`WebPMuxAssemble`]: fn.WebPMuxAssemble.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPMetadataKind {
+    /// ICC profile, in the `ICCP` chunk.
+    ICCP,
+    /// EXIF metadata, in the `EXIF` chunk.
+    EXIF,
+    /// XMP metadata, in the `XMP ` chunk.
+    XMP,
+}
+
+impl WebPMetadataKind {
+    pub fn fourcc(self) -> [u8; 4] {
+        match self {
+            WebPMetadataKind::ICCP => *b"ICCP",
+            WebPMetadataKind::EXIF => *b"EXIF",
+            WebPMetadataKind::XMP => *b"XMP ",
+        }
+    }
+}
+
+impl<'a> WebPMux<'a> {
+    /// Returns a copy of the metadata of the given kind, or `None` if there
+    /// is none.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` for any failure but `WEBP_MUX_NOT_FOUND`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{WebPMetadataKind, WebPMuxAssemble, WebPMuxCreate};
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let mut mux = WebPMuxCreate(data).unwrap();
+    /// mux.set_metadata(WebPMetadataKind::EXIF, b"Exif\0\0").unwrap();
+    /// mux.set_metadata(WebPMetadataKind::ICCP, b"icc profile").unwrap();
+    /// let with_metadata = WebPMuxAssemble(&mut mux).unwrap();
+    ///
+    /// // Strip EXIF, keep the ICC profile.
+    /// let mut mux = WebPMuxCreate(&with_metadata).unwrap();
+    /// mux.remove_metadata(WebPMetadataKind::EXIF).unwrap();
+    /// let stripped = WebPMuxAssemble(&mut mux).unwrap();
+    ///
+    /// let mux = WebPMuxCreate(&stripped).unwrap();
+    /// assert_eq!(mux.metadata(WebPMetadataKind::EXIF), Ok(None));
+    /// assert_eq!(mux.metadata(WebPMetadataKind::ICCP).unwrap().unwrap(), b"icc profile");
+    /// ```
+    pub fn metadata(&self, kind: WebPMetadataKind) -> Result<Option<Vec<u8>>, WebPMuxError> {
+        match WebPMuxGetChunk(self, &kind.fourcc()) {
+            Ok(data) => Ok(Some(data.to_vec())),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets the metadata of the given kind, replacing the existing one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if `data` is too large or the allocation failed.
+    pub fn set_metadata(
+        &mut self,
+        kind: WebPMetadataKind,
+        data: &[u8],
+    ) -> Result<(), WebPMuxError> {
+        WebPMuxSetChunk(self, &kind.fourcc(), data)
+    }
+
+    /// Removes the metadata of the given kind, returning it, or `None` if
+    /// there was none.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err` if the metadata couldn't be read or deleted, in which
+    /// case the mux is left as is.
+    pub fn remove_metadata(
+        &mut self,
+        kind: WebPMetadataKind,
+    ) -> Result<Option<Vec<u8>>, WebPMuxError> {
+        let data = match self.metadata(kind)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        WebPMuxDeleteChunk(self, &kind.fourcc())?;
+        Ok(Some(data))
+    }
+}
+
+/// Identifiers of the chunk kinds handled by the mux.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPChunkId {
+    WEBP_CHUNK_VP8X = 0,
+    WEBP_CHUNK_ICCP = 1,
+    WEBP_CHUNK_ANIM = 2,
+    WEBP_CHUNK_ANMF = 3,
+    /// Formerly `WEBP_CHUNK_FRGM`.
+    WEBP_CHUNK_DEPRECATED = 4,
+    WEBP_CHUNK_ALPHA = 5,
+    WEBP_CHUNK_IMAGE = 6,
+    WEBP_CHUNK_EXIF = 7,
+    WEBP_CHUNK_XMP = 8,
+    WEBP_CHUNK_UNKNOWN = 9,
+    WEBP_CHUNK_NIL = 10,
+}
+
+impl WebPChunkId {
+    pub fn from_raw(raw: sys::WebPChunkId) -> Self {
+        use self::WebPChunkId::*;
+
+        match raw {
+            sys::WEBP_CHUNK_VP8X => WEBP_CHUNK_VP8X,
+            sys::WEBP_CHUNK_ICCP => WEBP_CHUNK_ICCP,
+            sys::WEBP_CHUNK_ANIM => WEBP_CHUNK_ANIM,
+            sys::WEBP_CHUNK_ANMF => WEBP_CHUNK_ANMF,
+            4 => WEBP_CHUNK_DEPRECATED,
+            sys::WEBP_CHUNK_ALPHA => WEBP_CHUNK_ALPHA,
+            sys::WEBP_CHUNK_IMAGE => WEBP_CHUNK_IMAGE,
+            sys::WEBP_CHUNK_EXIF => WEBP_CHUNK_EXIF,
+            sys::WEBP_CHUNK_XMP => WEBP_CHUNK_XMP,
+            sys::WEBP_CHUNK_UNKNOWN => WEBP_CHUNK_UNKNOWN,
+            sys::WEBP_CHUNK_NIL => WEBP_CHUNK_NIL,
+            _ => panic!("WebPChunkId::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPChunkId {
+        self as sys::WebPChunkId
+    }
+}
+
+/// A frame or image of a mux object.
+///
+/// `B` is the bitstream: a raw `VP8 `/`VP8L` bitstream or a single-image
+/// WebP file when pushed, and an owned `WebpBox<[u8]>` when retrieved, so
+/// that retrieved frames can be pushed back as is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WebPMuxFrameInfo<B> {
+    pub bitstream: B,
+    /// Horizontal offset in the canvas. Odd values are snapped to even.
+    pub x_offset: u32,
+    /// Vertical offset in the canvas. Odd values are snapped to even.
+    pub y_offset: u32,
+    /// Duration in milliseconds.
+    pub duration: u32,
+    /// `WEBP_CHUNK_ANMF` for animation frames, or `WEBP_CHUNK_IMAGE` for a
+    /// still image.
+    pub id: WebPChunkId,
+    pub dispose_method: WebPMuxAnimDispose,
+    pub blend_method: WebPMuxAnimBlend,
+}
+
+impl<B> WebPMuxFrameInfo<B> {
+    /// Creates an animation frame at the top-left corner, with zero
+    /// duration, no disposal and alpha-blending.
+    pub fn new(bitstream: B) -> Self {
+        Self {
+            bitstream,
+            x_offset: 0,
+            y_offset: 0,
+            duration: 0,
+            id: WebPChunkId::WEBP_CHUNK_ANMF,
+            dispose_method: WebPMuxAnimDispose::WEBP_MUX_DISPOSE_NONE,
+            blend_method: WebPMuxAnimBlend::WEBP_MUX_BLEND,
+        }
+    }
+}
+
+/// Global parameters of an animation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WebPMuxAnimParams {
+    /// Background color of the canvas, with blue in the most significant
+    /// byte, then green, red and alpha in the least significant byte.
+    pub bgcolor: u32,
+    /// Number of iterations, 0 meaning infinite.
+    pub loop_count: u16,
+}
+
+/// Adds a frame at the end of the mux object. The bitstream is copied.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_INVALID_ARGUMENT` if the frame is invalid, e.g. `id`
+/// is not `WEBP_CHUNK_ANMF` or the offsets are too large.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{
+///     WebPEncodeLosslessRGBA, WebPMuxAnimParams, WebPMuxAssemble, WebPMuxFrameInfo,
+///     WebPMuxNew, WebPMuxPushFrame, WebPMuxSetAnimationParams,
+/// };
+///
+/// let red = WebPEncodeLosslessRGBA(&[255, 0, 0, 255], 1, 1, 4).unwrap();
+/// let blue = WebPEncodeLosslessRGBA(&[0, 0, 255, 255], 1, 1, 4).unwrap();
+///
+/// let mut mux = WebPMuxNew();
+/// for frame in &[red, blue] {
+///     let mut frame = WebPMuxFrameInfo::new(&frame[..]);
+///     frame.duration = 100;
+///     WebPMuxPushFrame(&mut mux, &frame).unwrap();
+/// }
+/// WebPMuxSetAnimationParams(&mut mux, &WebPMuxAnimParams::default()).unwrap();
+/// let data = WebPMuxAssemble(&mut mux).unwrap();
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPMuxPushFrame<B: AsRef<[u8]>>(
+    mux: &mut WebPMux,
+    frame: &WebPMuxFrameInfo<B>,
+) -> Result<(), WebPMuxError> {
+    let raw = sys::WebPMuxFrameInfo {
+        bitstream: webp_data(frame.bitstream.as_ref()),
+        x_offset: frame.x_offset as c_int,
+        y_offset: frame.y_offset as c_int,
+        duration: frame.duration as c_int,
+        id: frame.id.into_raw(),
+        dispose_method: frame.dispose_method.into_raw(),
+        blend_method: frame.blend_method.into_raw(),
+        pad: [0],
+    };
+    mux_result(unsafe { sys::WebPMuxPushFrame(mux.as_mut_ptr(), &raw, 1) })
+}
+
+/// Gets a copy of the `nth` frame, counting from 1. `nth` = 0 means the
+/// last frame.
+///
+/// For a still image, the image is returned with `id` set to
+/// `WEBP_CHUNK_IMAGE`.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_NOT_FOUND` if there are fewer than `nth` frames.
+#[allow(non_snake_case)]
+pub fn WebPMuxGetFrame(
+    mux: &WebPMux,
+    nth: u32,
+) -> Result<WebPMuxFrameInfo<WebpBox<[u8]>>, WebPMuxError> {
+    let mut raw: sys::WebPMuxFrameInfo = unsafe { mem::zeroed() };
+    mux_result(unsafe { sys::WebPMuxGetFrame(mux.as_ptr(), nth, &mut raw) })?;
+    if raw.bitstream.bytes.is_null() {
+        return Err(WebPMuxError::WEBP_MUX_MEMORY_ERROR);
+    }
+    let bitstream = unsafe {
+        WebpBox::from_raw(slice::from_raw_parts_mut(
+            raw.bitstream.bytes as *mut u8,
+            raw.bitstream.size,
+        ))
+    };
+    Ok(WebPMuxFrameInfo {
+        bitstream,
+        x_offset: raw.x_offset as u32,
+        y_offset: raw.y_offset as u32,
+        duration: raw.duration as u32,
+        id: WebPChunkId::from_raw(raw.id),
+        dispose_method: WebPMuxAnimDispose::from_raw(raw.dispose_method),
+        blend_method: WebPMuxAnimBlend::from_raw(raw.blend_method),
+    })
+}
+
+/// Deletes the `nth` frame, counting from 1. `nth` = 0 means the last
+/// frame.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_NOT_FOUND` if there are fewer than `nth` frames.
+#[allow(non_snake_case)]
+pub fn WebPMuxDeleteFrame(mux: &mut WebPMux, nth: u32) -> Result<(), WebPMuxError> {
+    mux_result(unsafe { sys::WebPMuxDeleteFrame(mux.as_mut_ptr(), nth) })
+}
+
+/// Sets the animation parameters, replacing the existing `ANIM` chunk.
+///
+/// ## Errors
+///
+/// Returns `Err` if the allocation failed.
+#[allow(non_snake_case)]
+pub fn WebPMuxSetAnimationParams(
+    mux: &mut WebPMux,
+    params: &WebPMuxAnimParams,
+) -> Result<(), WebPMuxError> {
+    let raw = sys::WebPMuxAnimParams {
+        bgcolor: params.bgcolor,
+        loop_count: c_int::from(params.loop_count),
+    };
+    mux_result(unsafe { sys::WebPMuxSetAnimationParams(mux.as_mut_ptr(), &raw) })
+}
+
+/// Gets the animation parameters.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_NOT_FOUND` if there is no `ANIM` chunk.
+#[allow(non_snake_case)]
+pub fn WebPMuxGetAnimationParams(mux: &WebPMux) -> Result<WebPMuxAnimParams, WebPMuxError> {
+    let mut raw = sys::WebPMuxAnimParams {
+        bgcolor: 0,
+        loop_count: 0,
+    };
+    mux_result(unsafe { sys::WebPMuxGetAnimationParams(mux.as_ptr(), &mut raw) })?;
+    Ok(WebPMuxAnimParams {
+        bgcolor: raw.bgcolor,
+        loop_count: raw.loop_count as u16,
+    })
+}
+
+/// Gets the number of chunks with the given `id`.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_INVALID_ARGUMENT` for `WEBP_CHUNK_DEPRECATED` and
+/// `WEBP_CHUNK_NIL`.
+#[allow(non_snake_case)]
+pub fn WebPMuxNumChunks(mux: &WebPMux, id: WebPChunkId) -> Result<u32, WebPMuxError> {
+    match id {
+        WebPChunkId::WEBP_CHUNK_DEPRECATED | WebPChunkId::WEBP_CHUNK_NIL => {
+            return Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT);
+        }
+        _ => {}
+    }
+    let mut num_elements: c_int = 0;
+    mux_result(unsafe { sys::WebPMuxNumChunks(mux.as_ptr(), id.into_raw(), &mut num_elements) })?;
+    Ok(num_elements as u32)
+}
+
+/// Sets the canvas size. The frames must fit in the canvas when the mux is
+/// assembled.
+///
+/// `(0, 0)` resets the canvas size to be derived from the image, for a
+/// still image.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_INVALID_ARGUMENT` if the size is too large, or only
+/// one of `width` and `height` is zero.
+#[cfg(feature = "0_5")]
+#[allow(non_snake_case)]
+pub fn WebPMuxSetCanvasSize(
+    mux: &mut WebPMux,
+    width: u32,
+    height: u32,
+) -> Result<(), WebPMuxError> {
+    if width > c_int::max_value() as u32 || height > c_int::max_value() as u32 {
+        return Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT);
+    }
+    mux_result(unsafe {
+        sys::WebPMuxSetCanvasSize(mux.as_mut_ptr(), width as c_int, height as c_int)
+    })
+}
+
+/// Gets the canvas size, as `(width, height)`.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_BAD_DATA` if the `VP8X` chunk or the canvas size is
+/// invalid.
+#[allow(non_snake_case)]
+pub fn WebPMuxGetCanvasSize(mux: &WebPMux) -> Result<(u32, u32), WebPMuxError> {
+    let mut width: c_int = 0;
+    let mut height: c_int = 0;
+    mux_result(unsafe { sys::WebPMuxGetCanvasSize(mux.as_ptr(), &mut width, &mut height) })?;
+    Ok((width as u32, height as u32))
+}
+
+/// Gets the feature flags from the `VP8X` chunk, or from the image if there
+/// is no `VP8X` chunk.
+///
+/// ## Errors
+///
+/// Returns `WEBP_MUX_BAD_DATA` if the `VP8X` chunk or the canvas size is
+/// invalid.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPFeatureFlags, WebPMuxCreate, WebPMuxGetFeatures};
+///
+/// let data: &[u8];
+/// # let data: &[u8] = include_bytes!("lena.webp");
+///
+/// let mux = WebPMuxCreate(data).unwrap();
+/// let flags = WebPMuxGetFeatures(&mux).unwrap();
+/// assert!(!flags.contains(WebPFeatureFlags::ANIMATION));
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPMuxGetFeatures(mux: &WebPMux) -> Result<WebPFeatureFlags, WebPMuxError> {
+    let mut flags: u32 = 0;
+    mux_result(unsafe { sys::WebPMuxGetFeatures(mux.as_ptr(), &mut flags) })?;
+    Ok(WebPFeatureFlags::from_bits_truncate(flags))
+}
+
+// FourCCs of the chunks interpreted by libwebp.
+const KNOWN_FOURCCS: [[u8; 4]; 9] = [
+    riff::FOURCC_VP8X,
+    riff::FOURCC_ICCP,
+    riff::FOURCC_ANIM,
+    riff::FOURCC_ANMF,
+    riff::FOURCC_ALPH,
+    riff::FOURCC_VP8,
+    riff::FOURCC_VP8L,
+    riff::FOURCC_EXIF,
+    riff::FOURCC_XMP,
+];
+
+// Maximum chunk payload accepted by libwebp.
+const MAX_CHUNK_PAYLOAD: usize = !0u32 as usize - 8 - 1;
+
+/// A chunk not interpreted by libwebp, such as a vendor chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WebPMuxChunk {
+    pub fourcc: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+// Prefixes of `WebPChunk` and `WebPMux` from libwebp's `src/mux/muxi.h`.
+// libwebp only gives access to the first unknown chunk of each FourCC, so the
+// list of top-level unknown chunks is walked directly.
+#[repr(C)]
+struct MuxChunk {
+    tag: u32,
+    owner: c_int,
+    data: sys::WebPData,
+    next: *mut MuxChunk,
+}
+
+#[repr(C)]
+struct MuxChunkLists {
+    images: *mut c_void,
+    iccp: *mut MuxChunk,
+    exif: *mut MuxChunk,
+    xmp: *mut MuxChunk,
+    anim: *mut MuxChunk,
+    vp8x: *mut MuxChunk,
+    unknown: *mut MuxChunk,
+}
+
+impl MuxChunk {
+    fn fourcc(&self) -> [u8; 4] {
+        self.tag.to_le_bytes()
+    }
+
+    fn data(&self) -> &[u8] {
+        if self.data.bytes.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.data.bytes, self.data.size) }
+    }
+}
+
+impl<'a> WebPMux<'a> {
+    fn unknown_list(&self) -> Vec<&MuxChunk> {
+        let mut chunks = Vec::new();
+        let mut chunk = unsafe { (*(self.as_ptr() as *const MuxChunkLists)).unknown };
+        while let Some(c) = unsafe { chunk.as_ref() } {
+            chunks.push(c);
+            chunk = c.next;
+        }
+        chunks
+    }
+
+    // Finds the position of the `nth` unknown chunk with `fourcc`, where
+    // `nth` is 1-based and 0 means the last one, as in libwebp.
+    fn unknown_position(&self, fourcc: &[u8; 4], nth: u32) -> Result<usize, WebPMuxError> {
+        if KNOWN_FOURCCS.contains(fourcc) {
+            return Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT);
+        }
+        let chunks = self.unknown_list();
+        let mut positions = (0..chunks.len()).filter(|&i| chunks[i].fourcc() == *fourcc);
+        let position = if nth == 0 {
+            positions.next_back()
+        } else {
+            positions.nth(nth as usize - 1)
+        };
+        position.ok_or(WebPMuxError::WEBP_MUX_NOT_FOUND)
+    }
+
+    /// Lists the top-level chunks not interpreted by libwebp, in order.
+    ///
+    /// Unlike [`WebPMuxGetChunk`], this returns every chunk of a repeated
+    /// FourCC.
+    ///
+    /// [`WebPMuxGetChunk`]: fn.WebPMuxGetChunk.html
+    pub fn unknown_chunks(&self) -> Vec<WebPMuxChunk> {
+        self.unknown_list()
+            .into_iter()
+            .map(|chunk| WebPMuxChunk {
+                fourcc: chunk.fourcc(),
+                data: chunk.data().to_vec(),
+            })
+            .collect()
+    }
+
+    /// Gets a reference to the data of the `nth` chunk with the given
+    /// `fourcc`, among those not interpreted by libwebp.
+    ///
+    /// As in libwebp, `nth` starts at 1, and 0 means the last one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `WEBP_MUX_NOT_FOUND` if there is no such chunk, and
+    /// `WEBP_MUX_INVALID_ARGUMENT` if `fourcc` is interpreted by libwebp.
+    pub fn unknown_chunk(&self, fourcc: &[u8; 4], nth: u32) -> Result<&[u8], WebPMuxError> {
+        let i = self.unknown_position(fourcc, nth)?;
+        Ok(self.unknown_list()[i].data())
+    }
+
+    /// Deletes the `nth` chunk with the given `fourcc`, among those not
+    /// interpreted by libwebp, keeping the others.
+    ///
+    /// As in libwebp, `nth` starts at 1, and 0 means the last one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `WEBP_MUX_NOT_FOUND` if there is no such chunk, and
+    /// `WEBP_MUX_INVALID_ARGUMENT` if `fourcc` is interpreted by libwebp.
+    pub fn delete_unknown_chunk(&mut self, fourcc: &[u8; 4], nth: u32) -> Result<(), WebPMuxError> {
+        let i = self.unknown_position(fourcc, nth)?;
+        unsafe {
+            let mut link = &mut (*(self.as_mut_ptr() as *mut MuxChunkLists)).unknown;
+            for _ in 0..i {
+                link = &mut (**link).next;
+            }
+            let chunk = *link;
+            *link = (*chunk).next;
+            if (*chunk).owner != 0 {
+                WebPFree((*chunk).data.bytes as *mut c_void);
+            }
+            WebPFree(chunk as *mut c_void);
+        }
+        Ok(())
+    }
+
+    /// Appends a chunk not interpreted by libwebp, keeping the existing ones,
+    /// even those with the same FourCC. The data is copied.
+    ///
+    /// [`WebPMuxSetChunk`] can only hold a single unknown chunk, so the
+    /// chunk is linked into the mux directly.
+    ///
+    /// [`WebPMuxSetChunk`]: fn.WebPMuxSetChunk.html
+    ///
+    /// ## Errors
+    ///
+    /// Returns `WEBP_MUX_INVALID_ARGUMENT` if `fourcc` is interpreted by
+    /// libwebp or `data` is too large.
+    ///
+    /// ## Panics
+    ///
+    /// Aborts on allocation failure.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use libwebp::{WebPMuxAssemble, WebPMuxCreate};
+    ///
+    /// let data: &[u8];
+    /// # let data: &[u8] = include_bytes!("lena.webp");
+    ///
+    /// let mut mux = WebPMuxCreate(data).unwrap();
+    /// mux.insert_unknown_chunk(b"vnd0", b"first").unwrap();
+    /// mux.insert_unknown_chunk(b"vnd0", b"second").unwrap();
+    /// assert_eq!(mux.unknown_chunk(b"vnd0", 2).unwrap(), b"second");
+    ///
+    /// mux.delete_unknown_chunk(b"vnd0", 1).unwrap();
+    /// assert_eq!(mux.unknown_chunks().len(), 1);
+    /// assert_eq!(mux.unknown_chunk(b"vnd0", 1).unwrap(), b"second");
+    /// let output = WebPMuxAssemble(&mut mux).unwrap();
+    /// ```
+    pub fn insert_unknown_chunk(
+        &mut self,
+        fourcc: &[u8; 4],
+        data: &[u8],
+    ) -> Result<(), WebPMuxError> {
+        if KNOWN_FOURCCS.contains(fourcc) || data.len() > MAX_CHUNK_PAYLOAD {
+            return Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT);
+        }
+        unsafe {
+            let bytes = if data.is_empty() {
+                ptr::null()
+            } else {
+                WebpBox::into_raw(WebpBox::from_slice(data)) as *const u8
+            };
+            let chunk = alloc_bytes(mem::size_of::<MuxChunk>()) as *mut MuxChunk;
+            ptr::write(
+                chunk,
+                MuxChunk {
+                    tag: u32::from_le_bytes(*fourcc),
+                    owner: 1,
+                    data: sys::WebPData {
+                        bytes,
+                        size: data.len(),
+                    },
+                    next: ptr::null_mut(),
+                },
+            );
+            let mut link = &mut (*(self.as_mut_ptr() as *mut MuxChunkLists)).unknown;
+            while !link.is_null() {
+                link = &mut (**link).next;
+            }
+            *link = chunk;
+        }
+        Ok(())
+    }
+}
+
+/// Metadata to embed with [`WebPEncodeWithMetadata`].
+///
+/// [`WebPEncodeWithMetadata`]: fn.WebPEncodeWithMetadata.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WebPMetadata<'a> {
+    /// ICC profile.
+    pub iccp: Option<&'a [u8]>,
+    /// EXIF metadata.
+    pub exif: Option<&'a [u8]>,
+    /// XMP packet.
+    pub xmp: Option<&'a [u8]>,
+}
+
+impl<'a> WebPMetadata<'a> {
+    fn chunks(&self) -> [(WebPMetadataKind, Option<&'a [u8]>); 3] {
+        [
+            (WebPMetadataKind::ICCP, self.iccp),
+            (WebPMetadataKind::EXIF, self.exif),
+            (WebPMetadataKind::XMP, self.xmp),
+        ]
+    }
+}
+
+/// Error returned by [`WebPEncodeWithMetadata`].
+///
+/// [`WebPEncodeWithMetadata`]: fn.WebPEncodeWithMetadata.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebPEncodeMetadataError {
+    /// The encoding itself failed.
+    Encoding(WebPEncodingError),
+    /// Embedding the metadata failed.
+    Mux(WebPMuxError),
+}
+
+impl fmt::Display for WebPEncodeMetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebPEncodeMetadataError::Encoding(e) => fmt::Display::fmt(e, f),
+            WebPEncodeMetadataError::Mux(e) => write!(f, "failed to embed metadata: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WebPEncodeMetadataError {}
+
+impl From<WebPEncodingError> for WebPEncodeMetadataError {
+    fn from(e: WebPEncodingError) -> Self {
+        WebPEncodeMetadataError::Encoding(e)
+    }
+}
+
+impl From<WebPMuxError> for WebPEncodeMetadataError {
+    fn from(e: WebPMuxError) -> Self {
+        WebPEncodeMetadataError::Mux(e)
+    }
+}
+
+/// Same as [`WebPEncode`], but embeds the given metadata into the output,
+/// producing an extended (`VP8X`) file if any is given.
+///
+/// [`WebPEncode`]: fn.WebPEncode.html
+///
+/// ## Errors
+///
+/// Returns `Err` if the encoding failed or the metadata couldn't be
+/// embedded.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::{WebPConfigInit, WebPEncodeWithMetadata, WebPMetadata, WebPPicture};
+///
+/// let mut picture = WebPPicture::new_argb(16, 16).unwrap();
+/// let icc_profile: &[u8] = b"...";
+/// let metadata = WebPMetadata {
+///     iccp: Some(icc_profile),
+///     ..WebPMetadata::default()
+/// };
+/// let data = WebPEncodeWithMetadata(&WebPConfigInit(), &mut picture, &metadata).unwrap();
+/// assert_eq!(&data[12..16], b"VP8X");
+/// ```
+#[allow(non_snake_case)]
+pub fn WebPEncodeWithMetadata(
+    config: &WebPConfig,
+    picture: &mut WebPPicture<'_>,
+    metadata: &WebPMetadata<'_>,
+) -> Result<WebpBox<[u8]>, WebPEncodeMetadataError> {
+    let data = WebPEncode(config, picture)?;
+    if *metadata == WebPMetadata::default() {
+        return Ok(data);
+    }
+    let mut mux = WebPMuxCreateBorrowed(&data)?;
+    for &(kind, chunk) in &metadata.chunks() {
+        if let Some(chunk) = chunk {
+            mux.set_metadata(kind, chunk)?;
+        }
+    }
+    Ok(WebPMuxAssemble(&mut mux)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::riff;
+    use crate::test_utils::{self, animation, chunk};
+    use crate::{
+        WebPConfigInit, WebPDecodeRGBA, WebPEncodeLosslessRGBA, WebPGetFeatures, WebPGetInfo,
+    };
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxCreate() {
+        let data = lena();
+        let mut mux = WebPMuxCreate(&data).unwrap();
+        drop(data);
+        let output = WebPMuxAssemble(&mut mux).unwrap();
+        assert_eq!(&output[..], &lena()[..]);
+
+        let data = WebPEncodeLosslessRGBA(&[255, 0, 0, 128], 1, 1, 4).unwrap();
+        let mut mux = WebPMuxCreateBorrowed(&data).unwrap();
+        let output = WebPMuxAssemble(&mut mux).unwrap();
+        drop(mux);
+        assert!(WebPGetFeatures(&output).unwrap().has_alpha);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxCreate_invalid() {
+        assert_eq!(
+            WebPMuxCreate(b"RIFF\0\0\0\0WEBP").unwrap_err(),
+            WebPMuxError::WEBP_MUX_BAD_DATA
+        );
+        assert!(WebPMuxCreateBorrowed(&lena()[..100]).is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxNew() {
+        let mut mux = WebPMuxNew();
+        assert!(WebPMuxAssemble(&mut mux).is_err());
+    }
+
+    #[test]
+    fn test_error_roundtrip() {
+        for raw in -4..=1 {
+            assert_eq!(WebPMuxError::from_raw(raw).into_raw(), raw);
+        }
+        assert!(mux_result(sys::WEBP_MUX_OK).is_ok());
+        assert_eq!(
+            mux_result(sys::WEBP_MUX_NOT_FOUND),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxSetChunk() {
+        let mut mux = WebPMuxCreate(&lena()).unwrap();
+        assert_eq!(
+            WebPMuxGetChunk(&mux, b"ICCP"),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        WebPMuxSetChunk(&mut mux, b"ICCP", b"icc").unwrap();
+        WebPMuxSetChunk(&mut mux, b"ICCP", b"icc2").unwrap();
+        assert_eq!(WebPMuxGetChunk(&mux, b"ICCP").unwrap(), b"icc2");
+        assert_eq!(
+            WebPMuxSetChunk(&mut mux, b"VP8 ", b"image"),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        assert_eq!(
+            WebPMuxGetChunk(&mux, b"VP8 "),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        WebPMuxDeleteChunk(&mut mux, b"ICCP").unwrap();
+        assert_eq!(
+            WebPMuxDeleteChunk(&mut mux, b"ICCP"),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        assert_eq!(&WebPMuxAssemble(&mut mux).unwrap()[..], &lena()[..]);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut mux = WebPMuxCreate(&lena()).unwrap();
+        for &kind in &[
+            WebPMetadataKind::ICCP,
+            WebPMetadataKind::EXIF,
+            WebPMetadataKind::XMP,
+        ] {
+            assert_eq!(mux.metadata(kind), Ok(None));
+            mux.set_metadata(kind, &kind.fourcc()).unwrap();
+        }
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        let vp8x = riff::parse(&data).unwrap().vp8x.unwrap();
+        assert!(vp8x.has_iccp() && vp8x.has_exif() && vp8x.has_xmp());
+        assert_eq!((vp8x.canvas_width, vp8x.canvas_height), (128, 128));
+
+        let mut mux = WebPMuxCreateBorrowed(&data).unwrap();
+        assert_eq!(
+            mux.metadata(WebPMetadataKind::XMP),
+            Ok(Some(b"XMP ".to_vec()))
+        );
+        assert_eq!(
+            mux.remove_metadata(WebPMetadataKind::EXIF),
+            Ok(Some(b"EXIF".to_vec()))
+        );
+        assert_eq!(mux.remove_metadata(WebPMetadataKind::EXIF), Ok(None));
+        mux.remove_metadata(WebPMetadataKind::XMP).unwrap().unwrap();
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        let vp8x = riff::parse(&data).unwrap().vp8x.unwrap();
+        assert!(vp8x.has_iccp() && !vp8x.has_exif() && !vp8x.has_xmp());
+
+        let mut mux = WebPMuxCreate(&data).unwrap();
+        mux.remove_metadata(WebPMetadataKind::ICCP)
+            .unwrap()
+            .unwrap();
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        assert_eq!(riff::parse(&data).unwrap().vp8x, None);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPEncodeWithMetadata() {
+        let (width, height, rgba) = WebPDecodeRGBA(&lena()).unwrap();
+        let mut picture = WebPPicture::new_argb(width, height).unwrap();
+        picture.import_rgba(&rgba, width * 4).unwrap();
+        let config = WebPConfigInit();
+
+        let plain =
+            WebPEncodeWithMetadata(&config, &mut picture, &WebPMetadata::default()).unwrap();
+        assert_eq!(&plain[..], &WebPEncode(&config, &mut picture).unwrap()[..]);
+        assert_eq!(riff::parse(&plain).unwrap().vp8x, None);
+
+        let metadata = WebPMetadata {
+            iccp: Some(b"icc"),
+            exif: Some(b"Exif\0\0"),
+            xmp: Some(b"<x:xmpmeta/>"),
+        };
+        let data = WebPEncodeWithMetadata(&config, &mut picture, &metadata).unwrap();
+        let info = riff::parse(&data).unwrap();
+        let vp8x = info.vp8x.unwrap();
+        assert!(vp8x.has_iccp() && vp8x.has_exif() && vp8x.has_xmp());
+        assert!(info.vp8_offset.is_some());
+
+        let mux = WebPMuxCreate(&data).unwrap();
+        assert_eq!(
+            mux.metadata(WebPMetadataKind::ICCP).unwrap().unwrap(),
+            b"icc"
+        );
+        assert_eq!(
+            mux.metadata(WebPMetadataKind::EXIF).unwrap().unwrap(),
+            b"Exif\0\0"
+        );
+        assert_eq!(
+            mux.metadata(WebPMetadataKind::XMP).unwrap().unwrap(),
+            b"<x:xmpmeta/>"
+        );
+        assert_eq!(
+            &WebPDecodeRGBA(&data).unwrap().2[..],
+            &WebPDecodeRGBA(&plain).unwrap().2[..]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxPushFrame() {
+        let mut mux = WebPMuxNew();
+        for (i, color) in [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 255]]
+            .iter()
+            .enumerate()
+        {
+            let bitstream = WebPEncodeLosslessRGBA(&color.repeat(4 * 4), 4, 4, 16).unwrap();
+            let blend_method = if i == 1 {
+                WebPMuxAnimBlend::WEBP_MUX_NO_BLEND
+            } else {
+                WebPMuxAnimBlend::WEBP_MUX_BLEND
+            };
+            let frame = WebPMuxFrameInfo {
+                x_offset: 2 * i as u32,
+                duration: 100 * (i as u32 + 1),
+                blend_method,
+                ..WebPMuxFrameInfo::new(&bitstream[..])
+            };
+            WebPMuxPushFrame(&mut mux, &frame).unwrap();
+        }
+        let params = WebPMuxAnimParams {
+            bgcolor: 0xff00_00ff,
+            loop_count: 2,
+        };
+        WebPMuxSetAnimationParams(&mut mux, &params).unwrap();
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        assert_eq!(&data[..], &animation()[..]);
+
+        let info = riff::parse(&data).unwrap();
+        assert!(info.vp8x.unwrap().has_animation());
+        assert_eq!(info.anmf_offsets.len(), 3);
+
+        let mux = WebPMuxCreate(&data).unwrap();
+        assert_eq!(
+            WebPMuxGetAnimationParams(&mux).unwrap(),
+            WebPMuxAnimParams {
+                bgcolor: 0xff00_00ff,
+                loop_count: 2,
+            }
+        );
+        assert_eq!(WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_ANMF), Ok(3));
+        assert_eq!(WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_ANIM), Ok(1));
+        assert_eq!(WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_ICCP), Ok(0));
+        assert!(WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_NIL).is_err());
+
+        let frame = WebPMuxGetFrame(&mux, 2).unwrap();
+        assert_eq!(frame.id, WebPChunkId::WEBP_CHUNK_ANMF);
+        assert_eq!((frame.x_offset, frame.y_offset), (2, 0));
+        assert_eq!(frame.duration, 200);
+        assert_eq!(
+            frame.dispose_method,
+            WebPMuxAnimDispose::WEBP_MUX_DISPOSE_NONE
+        );
+        assert_eq!(frame.blend_method, WebPMuxAnimBlend::WEBP_MUX_NO_BLEND);
+        let (_, _, rgba) = WebPDecodeRGBA(&frame.bitstream).unwrap();
+        assert_eq!(&rgba[..4], &[0, 255, 0, 128]);
+        assert_eq!(WebPMuxGetFrame(&mux, 0).unwrap().duration, 300);
+        assert_eq!(
+            WebPMuxGetFrame(&mux, 4).unwrap_err(),
+            WebPMuxError::WEBP_MUX_NOT_FOUND
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxDeleteFrame() {
+        // Reverse and retime the frames without re-encoding.
+        let mut mux = WebPMuxCreate(&animation()).unwrap();
+        let frames = (1..=3)
+            .map(|nth| WebPMuxGetFrame(&mux, nth).unwrap())
+            .collect::<Vec<_>>();
+        for _ in 0..3 {
+            WebPMuxDeleteFrame(&mut mux, 1).unwrap();
+        }
+        assert_eq!(
+            WebPMuxDeleteFrame(&mut mux, 1),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        for frame in frames.iter().rev() {
+            let frame = WebPMuxFrameInfo {
+                duration: 50,
+                dispose_method: WebPMuxAnimDispose::WEBP_MUX_DISPOSE_BACKGROUND,
+                blend_method: WebPMuxAnimBlend::WEBP_MUX_NO_BLEND,
+                ..frame.clone()
+            };
+            WebPMuxPushFrame(&mut mux, &frame).unwrap();
+        }
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+
+        let mux = WebPMuxCreate(&data).unwrap();
+        for (nth, expected) in (1..=3).zip(frames.iter().rev()) {
+            let frame = WebPMuxGetFrame(&mux, nth).unwrap();
+            assert_eq!(frame.bitstream, expected.bitstream);
+            assert_eq!(frame.x_offset, expected.x_offset);
+            assert_eq!(frame.duration, 50);
+            assert_eq!(
+                frame.dispose_method,
+                WebPMuxAnimDispose::WEBP_MUX_DISPOSE_BACKGROUND
+            );
+            assert_eq!(frame.blend_method, WebPMuxAnimBlend::WEBP_MUX_NO_BLEND);
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxGetFrame_image() {
+        let mux = WebPMuxCreate(&lena()).unwrap();
+        let frame = WebPMuxGetFrame(&mux, 1).unwrap();
+        assert_eq!(frame.id, WebPChunkId::WEBP_CHUNK_IMAGE);
+        assert_eq!(WebPGetInfo(&frame.bitstream), Ok((128, 128)));
+        assert_eq!(
+            WebPMuxGetAnimationParams(&mux),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+
+        let mut mux = WebPMuxNew();
+        let frame = WebPMuxFrameInfo {
+            id: WebPChunkId::WEBP_CHUNK_IMAGE,
+            ..WebPMuxFrameInfo::new(lena())
+        };
+        assert_eq!(
+            WebPMuxPushFrame(&mut mux, &frame),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxGetFeatures() {
+        let mux = WebPMuxCreate(&lena()).unwrap();
+        assert_eq!(WebPMuxGetFeatures(&mux), Ok(WebPFeatureFlags::empty()));
+        assert_eq!(WebPMuxGetCanvasSize(&mux), Ok((128, 128)));
+
+        let mux = WebPMuxCreate(&animation()).unwrap();
+        assert_eq!(
+            WebPMuxGetFeatures(&mux),
+            Ok(WebPFeatureFlags::ANIMATION | WebPFeatureFlags::ALPHA)
+        );
+        assert_eq!(WebPMuxGetCanvasSize(&mux), Ok((8, 4)));
+
+        let mut mux = WebPMuxCreate(&lena()).unwrap();
+        mux.set_metadata(WebPMetadataKind::XMP, b"<x/>").unwrap();
+        mux.set_metadata(WebPMetadataKind::ICCP, b"icc").unwrap();
+        let mux = WebPMuxCreate(&WebPMuxAssemble(&mut mux).unwrap()).unwrap();
+        assert_eq!(
+            WebPMuxGetFeatures(&mux),
+            Ok(WebPFeatureFlags::XMP | WebPFeatureFlags::ICCP)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "0_5")]
+    #[allow(non_snake_case)]
+    fn test_WebPMuxSetCanvasSize() {
+        let mut mux = WebPMuxCreate(&animation()).unwrap();
+        WebPMuxSetCanvasSize(&mut mux, 20, 10).unwrap();
+        assert_eq!(WebPMuxGetCanvasSize(&mux), Ok((20, 10)));
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        let vp8x = riff::parse(&data).unwrap().vp8x.unwrap();
+        assert_eq!((vp8x.canvas_width, vp8x.canvas_height), (20, 10));
+
+        // The last frame spans 4..8 horizontally.
+        let mut mux = WebPMuxCreate(&data).unwrap();
+        WebPMuxSetCanvasSize(&mut mux, 7, 4).unwrap();
+        assert_eq!(
+            WebPMuxAssemble(&mut mux).unwrap_err(),
+            WebPMuxError::WEBP_MUX_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            WebPMuxSetCanvasSize(&mut mux, 0, 4),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        assert_eq!(
+            WebPMuxSetCanvasSize(&mut mux, u32::max_value(), 4),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        WebPMuxSetCanvasSize(&mut mux, 0, 0).unwrap();
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        let vp8x = riff::parse(&data).unwrap().vp8x.unwrap();
+        assert_eq!((vp8x.canvas_width, vp8x.canvas_height), (8, 4));
+    }
+
+    // Byte ranges of the unknown chunks, including their headers and padding.
+    fn unknown_chunk_bytes(data: &[u8]) -> Vec<&[u8]> {
+        riff::chunks(data)
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .filter(|chunk| !KNOWN_FOURCCS.contains(&chunk.fourcc()))
+            .map(|chunk| {
+                let end = chunk.payload_offset() + chunk.size() + chunk.size() % 2;
+                &data[chunk.offset()..end]
+            })
+            .collect()
+    }
+
+    fn vendor_file() -> Vec<u8> {
+        let lena = lena();
+        let vp8x = [0, 0, 0, 0, 127, 0, 0, 127, 0, 0];
+        test_utils::riff(&[
+            chunk(b"VP8X", &vp8x),
+            lena[12..].to_vec(),
+            chunk(b"vndA", b"odd"),
+            chunk(b"vndB", &[0xff; 4]),
+            chunk(b"vndA", b"\0\0"),
+        ])
+    }
+
+    #[test]
+    fn test_unknown_chunks_roundtrip() {
+        let data = vendor_file();
+        let mut mux = WebPMuxCreate(&data).unwrap();
+        assert_eq!(&WebPMuxAssemble(&mut mux).unwrap()[..], &data[..]);
+        assert_eq!(
+            mux.unknown_chunks(),
+            vec![
+                WebPMuxChunk {
+                    fourcc: *b"vndA",
+                    data: b"odd".to_vec(),
+                },
+                WebPMuxChunk {
+                    fourcc: *b"vndB",
+                    data: vec![0xff; 4],
+                },
+                WebPMuxChunk {
+                    fourcc: *b"vndA",
+                    data: b"\0\0".to_vec(),
+                },
+            ]
+        );
+        assert_eq!(
+            WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_UNKNOWN),
+            Ok(3)
+        );
+
+        // Metadata rewrite.
+        let mut mux = WebPMuxCreateBorrowed(&data).unwrap();
+        mux.set_metadata(WebPMetadataKind::EXIF, b"Exif\0\0GPS")
+            .unwrap();
+        mux.set_metadata(WebPMetadataKind::ICCP, b"icc").unwrap();
+        let with_metadata = WebPMuxAssemble(&mut mux).unwrap();
+        let mut mux = WebPMuxCreate(&with_metadata).unwrap();
+        mux.remove_metadata(WebPMetadataKind::EXIF)
+            .unwrap()
+            .unwrap();
+        let rewritten = WebPMuxAssemble(&mut mux).unwrap();
+        assert_ne!(&rewritten[..], &data[..]);
+        assert_eq!(unknown_chunk_bytes(&rewritten), unknown_chunk_bytes(&data));
+        assert_eq!(unknown_chunk_bytes(&data).len(), 3);
+    }
+
+    #[test]
+    fn test_unknown_chunks_edit() {
+        let mut mux = WebPMuxCreate(&lena()).unwrap();
+        assert_eq!(mux.unknown_chunks(), vec![]);
+        mux.insert_unknown_chunk(b"vndA", b"odd").unwrap();
+        // libwebp itself can't add a second unknown chunk.
+        assert!(WebPMuxSetChunk(&mut mux, b"vndB", &[0xff; 4]).is_err());
+        mux.insert_unknown_chunk(b"vndB", &[0xff; 4]).unwrap();
+        mux.insert_unknown_chunk(b"vndA", b"\0\0").unwrap();
+        assert_eq!(mux.unknown_chunk(b"vndA", 1).unwrap(), b"odd");
+        assert_eq!(mux.unknown_chunk(b"vndA", 2).unwrap(), b"\0\0");
+        assert_eq!(mux.unknown_chunk(b"vndA", 0).unwrap(), b"\0\0");
+        assert_eq!(
+            mux.unknown_chunk(b"vndA", 3),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        assert_eq!(
+            mux.insert_unknown_chunk(b"EXIF", b""),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        assert_eq!(
+            mux.unknown_chunk(b"EXIF", 1),
+            Err(WebPMuxError::WEBP_MUX_INVALID_ARGUMENT)
+        );
+        let data = WebPMuxAssemble(&mut mux).unwrap();
+        assert_eq!(
+            unknown_chunk_bytes(&data),
+            unknown_chunk_bytes(&vendor_file())
+        );
+
+        WebPMuxDeleteChunk(&mut mux, b"vndA").unwrap();
+        let chunks = mux.unknown_chunks();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(&chunks[0].fourcc, b"vndB");
+    }
+
+    #[test]
+    fn test_delete_unknown_chunk() {
+        let data = vendor_file();
+        let expected = unknown_chunk_bytes(&data);
+        // `vndA` chunks are the first and the last ones.
+        for &(nth, deleted) in &[(1, 0), (2, 2), (0, 2)] {
+            let mut mux = WebPMuxCreateBorrowed(&data).unwrap();
+            mux.delete_unknown_chunk(b"vndA", nth).unwrap();
+            let output = WebPMuxAssemble(&mut mux).unwrap();
+            let mut kept = expected.clone();
+            kept.remove(deleted);
+            assert_eq!(unknown_chunk_bytes(&output), kept);
+        }
+
+        let mut mux = WebPMuxCreate(&data).unwrap();
+        assert_eq!(
+            mux.delete_unknown_chunk(b"vndC", 1),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        mux.delete_unknown_chunk(b"vndB", 1).unwrap();
+        mux.delete_unknown_chunk(b"vndA", 1).unwrap();
+        mux.delete_unknown_chunk(b"vndA", 1).unwrap();
+        assert_eq!(
+            mux.delete_unknown_chunk(b"vndA", 0),
+            Err(WebPMuxError::WEBP_MUX_NOT_FOUND)
+        );
+        assert_eq!(&WebPMuxAssemble(&mut mux).unwrap()[..], &lena()[..]);
+    }
+
+    #[test]
+    fn test_insert_unknown_chunk_new() {
+        let mut mux = WebPMuxNew();
+        mux.insert_unknown_chunk(b"vndA", b"").unwrap();
+        mux.insert_unknown_chunk(b"vndA", b"odd").unwrap();
+        assert_eq!(mux.unknown_chunk(b"vndA", 1).unwrap(), b"");
+        assert_eq!(mux.unknown_chunks().len(), 2);
+        mux.delete_unknown_chunk(b"vndA", 1).unwrap();
+        assert_eq!(
+            WebPMuxNumChunks(&mux, WebPChunkId::WEBP_CHUNK_UNKNOWN),
+            Ok(1)
+        );
+        assert_eq!(WebPMuxGetChunk(&mux, b"vndA").unwrap(), b"odd");
+    }
+}
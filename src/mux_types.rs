@@ -0,0 +1,76 @@
+use libwebp_sys as sys;
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// Features of a WebP file, as recorded in the `VP8X` chunk.
+    pub struct WebPFeatureFlags: u32 {
+        const ANIMATION = sys::ANIMATION_FLAG;
+        const XMP = sys::XMP_FLAG;
+        const EXIF = sys::EXIF_FLAG;
+        const ALPHA = sys::ALPHA_FLAG;
+        const ICCP = sys::ICCP_FLAG;
+    }
+}
+
+/// Dispose method of an animation frame, applied after it is displayed.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPMuxAnimDispose {
+    /// Leave the canvas as is.
+    WEBP_MUX_DISPOSE_NONE = 0,
+    /// Clear the frame area to the background color.
+    WEBP_MUX_DISPOSE_BACKGROUND = 1,
+}
+
+impl WebPMuxAnimDispose {
+    pub fn from_raw(raw: sys::WebPMuxAnimDispose) -> Self {
+        use self::WebPMuxAnimDispose::*;
+
+        match raw {
+            sys::WEBP_MUX_DISPOSE_NONE => WEBP_MUX_DISPOSE_NONE,
+            sys::WEBP_MUX_DISPOSE_BACKGROUND => WEBP_MUX_DISPOSE_BACKGROUND,
+            _ => panic!("WebPMuxAnimDispose::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPMuxAnimDispose {
+        use self::WebPMuxAnimDispose::*;
+
+        match self {
+            WEBP_MUX_DISPOSE_NONE => sys::WEBP_MUX_DISPOSE_NONE,
+            WEBP_MUX_DISPOSE_BACKGROUND => sys::WEBP_MUX_DISPOSE_BACKGROUND,
+        }
+    }
+}
+
+/// Blend operation of an animation frame.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebPMuxAnimBlend {
+    /// Alpha-blend the frame onto the canvas.
+    WEBP_MUX_BLEND = 0,
+    /// Overwrite the frame area of the canvas.
+    WEBP_MUX_NO_BLEND = 1,
+}
+
+impl WebPMuxAnimBlend {
+    pub fn from_raw(raw: sys::WebPMuxAnimBlend) -> Self {
+        use self::WebPMuxAnimBlend::*;
+
+        match raw {
+            sys::WEBP_MUX_BLEND => WEBP_MUX_BLEND,
+            sys::WEBP_MUX_NO_BLEND => WEBP_MUX_NO_BLEND,
+            _ => panic!("WebPMuxAnimBlend::from_raw: unknown value {:?}", raw),
+        }
+    }
+
+    pub fn into_raw(self) -> sys::WebPMuxAnimBlend {
+        use self::WebPMuxAnimBlend::*;
+
+        match self {
+            WEBP_MUX_BLEND => sys::WEBP_MUX_BLEND,
+            WEBP_MUX_NO_BLEND => sys::WEBP_MUX_NO_BLEND,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Typed pixel layer on top of the `rgb` crate.
+//!
+//! The functions in this module pick the channel layout from the pixel type
+//! at compile time, and delegate to the corresponding `WebPEncode*` and
+//! `WebPDecode*` functions without copying.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use libwebp::pixel;
+//! use rgb::RGBA8;
+//!
+//! let pixels = vec![RGBA8::new(255, 0, 0, 255); 4 * 4];
+//! let data = pixel::encode_lossless(&pixels, 4, 4).unwrap();
+//!
+//! let (width, height, decoded) = pixel::decode::<RGBA8>(&data).unwrap();
+//! assert_eq!((width, height), (4, 4));
+//! assert_eq!(&decoded[..], &pixels[..]);
+//! ```
+
+use rgb::alt::{BGR8, BGRA8};
+use rgb::{RGB8, RGBA8};
+
+use crate::boxed::WebpBox;
+use crate::decode::*;
+use crate::encode::*;
+use crate::error::WebPSimpleError;
+
+/// A pixel type with a channel layout supported by libwebp.
+///
+/// Implemented for [`RGB8`], [`RGBA8`], [`BGR8`] and [`BGRA8`].
+///
+/// [`RGB8`]: https://docs.rs/rgb/0.8/rgb/type.RGB8.html
+/// [`RGBA8`]: https://docs.rs/rgb/0.8/rgb/type.RGBA8.html
+/// [`BGR8`]: https://docs.rs/rgb/0.8/rgb/alt/type.BGR8.html
+/// [`BGRA8`]: https://docs.rs/rgb/0.8/rgb/alt/type.BGRA8.html
+pub trait Pixel: bytemuck::Pod {
+    /// Encodes packed samples of this layout with lossy compression.
+    fn encode_bytes(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        quality_factor: f32,
+    ) -> Result<WebpBox<[u8]>, WebPSimpleError>;
+
+    /// Encodes packed samples of this layout with lossless compression.
+    fn encode_lossless_bytes(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+    ) -> Result<WebpBox<[u8]>, WebPSimpleError>;
+
+    /// Decodes into packed samples of this layout.
+    fn decode_bytes(data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPSimpleError>;
+}
+
+macro_rules! impl_pixel {
+    ($ty:ty, $encode:ident, $encode_lossless:ident, $decode:ident) => {
+        impl Pixel for $ty {
+            fn encode_bytes(
+                data: &[u8],
+                width: u32,
+                height: u32,
+                stride: u32,
+                quality_factor: f32,
+            ) -> Result<WebpBox<[u8]>, WebPSimpleError> {
+                $encode(data, width, height, stride, quality_factor)
+            }
+
+            fn encode_lossless_bytes(
+                data: &[u8],
+                width: u32,
+                height: u32,
+                stride: u32,
+            ) -> Result<WebpBox<[u8]>, WebPSimpleError> {
+                $encode_lossless(data, width, height, stride)
+            }
+
+            fn decode_bytes(data: &[u8]) -> Result<(u32, u32, WebpBox<[u8]>), WebPSimpleError> {
+                $decode(data)
+            }
+        }
+    };
+}
+
+impl_pixel!(RGB8, WebPEncodeRGB, WebPEncodeLosslessRGB, WebPDecodeRGB);
+impl_pixel!(
+    RGBA8,
+    WebPEncodeRGBA,
+    WebPEncodeLosslessRGBA,
+    WebPDecodeRGBA
+);
+impl_pixel!(BGR8, WebPEncodeBGR, WebPEncodeLosslessBGR, WebPDecodeBGR);
+impl_pixel!(
+    BGRA8,
+    WebPEncodeBGRA,
+    WebPEncodeLosslessBGRA,
+    WebPDecodeBGRA
+);
+
+fn pixel_stride<P: Pixel>(width: u32) -> u32 {
+    width
+        .checked_mul(std::mem::size_of::<P>() as u32)
+        .expect("stride overflow")
+}
+
+/// Encodes `pixels` with lossy compression. `quality_factor` ranges from 0
+/// (small output, low quality) to 100 (large output, high quality).
+///
+/// Rows are expected to be tightly packed, i.e. the stride is `width`
+/// pixels.
+///
+/// ## Errors
+///
+/// Returns `Err` if the encoding failed.
+///
+/// ## Panics
+///
+/// Panics when `pixels` has a wrong size.
+pub fn encode<P: Pixel>(
+    pixels: &[P],
+    width: u32,
+    height: u32,
+    quality_factor: f32,
+) -> Result<WebpBox<[u8]>, WebPSimpleError> {
+    let stride = pixel_stride::<P>(width);
+    P::encode_bytes(
+        bytemuck::cast_slice(pixels),
+        width,
+        height,
+        stride,
+        quality_factor,
+    )
+}
+
+/// Encodes `pixels` with lossless compression.
+///
+/// Rows are expected to be tightly packed, i.e. the stride is `width`
+/// pixels.
+///
+/// ## Errors
+///
+/// Returns `Err` if the encoding failed.
+///
+/// ## Panics
+///
+/// Panics when `pixels` has a wrong size.
+pub fn encode_lossless<P: Pixel>(
+    pixels: &[P],
+    width: u32,
+    height: u32,
+) -> Result<WebpBox<[u8]>, WebPSimpleError> {
+    let stride = pixel_stride::<P>(width);
+    P::encode_lossless_bytes(bytemuck::cast_slice(pixels), width, height, stride)
+}
+
+/// Decodes WebP images pointed to by `data` and returns the pixels, along
+/// with the dimensions (width and height).
+///
+/// The buffer returned by libwebp is reinterpreted in place.
+///
+/// ## Errors
+///
+/// Returns `Err` if `data` doesn't contain a valid WebP image.
+pub fn decode<P: Pixel>(data: &[u8]) -> Result<(u32, u32, WebpBox<[P]>), WebPSimpleError> {
+    let (width, height, mut buf) = P::decode_bytes(data)?;
+    // Cast while `buf` still owns the allocation, so that it is freed if the
+    // cast panics.
+    let pixels: *mut [P] = bytemuck::cast_slice_mut(&mut buf[..]);
+    WebpBox::into_raw(buf);
+    Ok((width, height, unsafe { WebpBox::from_raw(pixels) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    #[test]
+    fn test_decode() {
+        let (width, height, rgb) = decode::<RGB8>(&lena()).unwrap();
+        assert_eq!((width, height), (128, 128));
+        assert_eq!(rgb.len(), 128 * 128);
+        assert_eq!(rgb[0], RGB8::new(226, 158, 113));
+
+        let (_, _, bgra) = decode::<BGRA8>(&lena()).unwrap();
+        assert_eq!(
+            (bgra[0].b, bgra[0].g, bgra[0].r, bgra[0].a),
+            (113, 158, 226, 255)
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let (width, height, rgba) = decode::<RGBA8>(&lena()).unwrap();
+        let data = encode(&rgba, width, height, 90.0).unwrap();
+        let (_, _, expected) = WebPDecodeRGBA(&data).unwrap();
+        let (_, _, bgr) = decode::<BGR8>(&data).unwrap();
+        for (px, expected) in bgr.iter().zip(expected.chunks(4)) {
+            assert_eq!([px.r, px.g, px.b], expected[..3]);
+        }
+    }
+
+    #[test]
+    fn test_encode_lossless() {
+        let pixels = (0..7 * 5)
+            .map(|i| BGR8 {
+                b: i as u8,
+                g: 2 * i as u8,
+                r: 255 - i as u8,
+            })
+            .collect::<Vec<_>>();
+        let data = encode_lossless(&pixels, 7, 5).unwrap();
+        let (width, height, decoded) = decode::<BGR8>(&data).unwrap();
+        assert_eq!((width, height), (7, 5));
+        assert_eq!(&decoded[..], &pixels[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_encode_wrong_size() {
+        let pixels = vec![RGBA8::default(); 10];
+        let _ = encode(&pixels, 4, 4, 75.0);
+    }
+}
@@ -0,0 +1,619 @@
+//! Pure-Rust parser for the RIFF container of WebP files.
+//!
+//! Nothing here calls into libwebp, so untrusted input can be inspected
+//! and triaged before reaching the decoder. Chunk payloads are borrowed from
+//! the input.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use libwebp::riff;
+//!
+//! let data: &[u8] = include_bytes!("lena.webp");
+//!
+//! for chunk in riff::chunks(data).unwrap() {
+//!     let chunk = chunk.unwrap();
+//!     println!("{:?} at {}: {} bytes", chunk.fourcc_str(), chunk.offset(), chunk.size());
+//! }
+//!
+//! let info = riff::parse(data).unwrap();
+//! assert_eq!(info.vp8_offset, Some(12));
+//! assert!(info.vp8x.is_none());
+//! ```
+
+use crate::error::{RiffError, RiffErrorKind};
+
+pub const FOURCC_VP8: [u8; 4] = *b"VP8 ";
+pub const FOURCC_VP8L: [u8; 4] = *b"VP8L";
+pub const FOURCC_VP8X: [u8; 4] = *b"VP8X";
+pub const FOURCC_ALPH: [u8; 4] = *b"ALPH";
+pub const FOURCC_ANIM: [u8; 4] = *b"ANIM";
+pub const FOURCC_ANMF: [u8; 4] = *b"ANMF";
+pub const FOURCC_ICCP: [u8; 4] = *b"ICCP";
+pub const FOURCC_EXIF: [u8; 4] = *b"EXIF";
+pub const FOURCC_XMP: [u8; 4] = *b"XMP ";
+
+const RIFF_HEADER_SIZE: usize = 12;
+const CHUNK_HEADER_SIZE: usize = 8;
+const VP8X_SIZE: usize = 10;
+const ANIM_SIZE: usize = 6;
+const ANMF_SIZE: usize = 16;
+
+fn read_u16(data: &[u8]) -> u16 {
+    u16::from(data[0]) | u16::from(data[1]) << 8
+}
+
+fn read_u24(data: &[u8]) -> u32 {
+    u32::from(data[0]) | u32::from(data[1]) << 8 | u32::from(data[2]) << 16
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    read_u24(data) | u32::from(data[3]) << 24
+}
+
+fn error(kind: RiffErrorKind, offset: usize) -> RiffError {
+    RiffError { kind, offset }
+}
+
+/// Payload of a `VP8X` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VP8XHeader {
+    /// Raw feature flags.
+    pub flags: u8,
+    pub canvas_width: u32,
+    pub canvas_height: u32,
+}
+
+impl VP8XHeader {
+    pub const ANIMATION_FLAG: u8 = 0x02;
+    pub const XMP_FLAG: u8 = 0x04;
+    pub const EXIF_FLAG: u8 = 0x08;
+    pub const ALPHA_FLAG: u8 = 0x10;
+    pub const ICCP_FLAG: u8 = 0x20;
+
+    fn parse(payload: &[u8]) -> Self {
+        Self {
+            flags: payload[0],
+            canvas_width: read_u24(&payload[4..]) + 1,
+            canvas_height: read_u24(&payload[7..]) + 1,
+        }
+    }
+
+    pub fn has_animation(&self) -> bool {
+        self.flags & Self::ANIMATION_FLAG != 0
+    }
+
+    pub fn has_xmp(&self) -> bool {
+        self.flags & Self::XMP_FLAG != 0
+    }
+
+    pub fn has_exif(&self) -> bool {
+        self.flags & Self::EXIF_FLAG != 0
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.flags & Self::ALPHA_FLAG != 0
+    }
+
+    pub fn has_iccp(&self) -> bool {
+        self.flags & Self::ICCP_FLAG != 0
+    }
+}
+
+/// Payload of an `ANIM` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ANIMHeader {
+    /// Background color, as stored: blue in the lowest byte, then green,
+    /// red and alpha.
+    pub bgcolor: u32,
+    /// Number of iterations, 0 meaning infinite.
+    pub loop_count: u16,
+}
+
+impl ANIMHeader {
+    fn parse(payload: &[u8]) -> Self {
+        Self {
+            bgcolor: read_u32(payload),
+            loop_count: read_u16(&payload[4..]),
+        }
+    }
+}
+
+/// Fixed-size header of an `ANMF` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ANMFHeader {
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Duration in milliseconds.
+    pub duration: u32,
+    /// Whether the frame area is cleared to the background color after
+    /// display.
+    pub dispose_to_background: bool,
+    /// Whether the frame is alpha-blended with the previous canvas.
+    pub blend: bool,
+}
+
+impl ANMFHeader {
+    fn parse(payload: &[u8]) -> Self {
+        Self {
+            x_offset: read_u24(payload) * 2,
+            y_offset: read_u24(&payload[3..]) * 2,
+            width: read_u24(&payload[6..]) + 1,
+            height: read_u24(&payload[9..]) + 1,
+            duration: read_u24(&payload[12..]),
+            dispose_to_background: payload[15] & 0x01 != 0,
+            blend: payload[15] & 0x02 == 0,
+        }
+    }
+}
+
+/// A chunk borrowed from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chunk<'a> {
+    fourcc: [u8; 4],
+    offset: usize,
+    payload: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    pub fn fourcc(&self) -> [u8; 4] {
+        self.fourcc
+    }
+
+    /// The FourCC, with invalid UTF-8 replaced.
+    pub fn fourcc_str(&self) -> String {
+        String::from_utf8_lossy(&self.fourcc).into_owned()
+    }
+
+    /// Byte offset of the chunk header in the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Byte offset of the payload in the input.
+    pub fn payload_offset(&self) -> usize {
+        self.offset + CHUNK_HEADER_SIZE
+    }
+
+    /// Size of the payload, excluding the padding byte.
+    pub fn size(&self) -> usize {
+        self.payload.len()
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Parses the payload of a `VP8X` chunk.
+    pub fn vp8x(&self) -> Option<VP8XHeader> {
+        if self.fourcc == FOURCC_VP8X {
+            Some(VP8XHeader::parse(self.payload))
+        } else {
+            None
+        }
+    }
+
+    /// Parses the payload of an `ANIM` chunk.
+    pub fn anim(&self) -> Option<ANIMHeader> {
+        if self.fourcc == FOURCC_ANIM {
+            Some(ANIMHeader::parse(self.payload))
+        } else {
+            None
+        }
+    }
+
+    /// Parses the header of an `ANMF` chunk.
+    pub fn anmf(&self) -> Option<ANMFHeader> {
+        if self.fourcc == FOURCC_ANMF {
+            Some(ANMFHeader::parse(self.payload))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the chunks nested in an `ANMF` chunk, after its header.
+    pub fn frame_chunks(&self) -> Option<Chunks<'a>> {
+        if self.fourcc == FOURCC_ANMF {
+            let data = &self.payload[ANMF_SIZE..];
+            Some(Chunks {
+                data,
+                base: self.payload_offset() + ANMF_SIZE,
+                pos: 0,
+                end: data.len(),
+                first: false,
+                done: false,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the chunks of a RIFF container, see [`chunks`].
+///
+/// Stops after the first error.
+///
+/// [`chunks`]: fn.chunks.html
+#[derive(Debug, Clone)]
+pub struct Chunks<'a> {
+    data: &'a [u8],
+    // Offset of `data` in the input, for error reporting.
+    base: usize,
+    pos: usize,
+    end: usize,
+    // Whether the first top-level chunk is yet to be read.
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Chunks<'a> {
+    fn next_chunk(&mut self) -> Result<Chunk<'a>, RiffError> {
+        let pos = self.pos;
+        let offset = self.base + pos;
+        if self.end - pos < CHUNK_HEADER_SIZE {
+            return Err(error(RiffErrorKind::Truncated, offset));
+        }
+        if self.data.len() < pos + CHUNK_HEADER_SIZE {
+            return Err(error(RiffErrorKind::Truncated, offset));
+        }
+        let mut fourcc = [0; 4];
+        fourcc.copy_from_slice(&self.data[pos..pos + 4]);
+        let size = read_u32(&self.data[pos + 4..]) as usize;
+
+        let payload_start = pos + CHUNK_HEADER_SIZE;
+        if size > self.end - payload_start {
+            return Err(error(RiffErrorKind::ChunkTooLarge(fourcc), offset));
+        }
+        let payload_end = payload_start + size;
+        let padded_end = payload_end + (size & 1);
+        if padded_end > self.end {
+            return Err(error(RiffErrorKind::MissingPadding(fourcc), offset));
+        }
+        if padded_end > self.data.len() {
+            return Err(error(RiffErrorKind::Truncated, offset));
+        }
+
+        if self.first {
+            if fourcc != FOURCC_VP8 && fourcc != FOURCC_VP8L && fourcc != FOURCC_VP8X {
+                return Err(error(RiffErrorKind::UnexpectedFirstChunk(fourcc), offset));
+            }
+            self.first = false;
+        }
+        let min_size = match &fourcc {
+            b"VP8X" => VP8X_SIZE,
+            b"ANIM" => ANIM_SIZE,
+            b"ANMF" => ANMF_SIZE,
+            _ => 0,
+        };
+        if size < min_size {
+            return Err(error(RiffErrorKind::ChunkTooSmall(fourcc), offset));
+        }
+
+        self.pos = padded_end;
+        Ok(Chunk {
+            fourcc,
+            offset,
+            payload: &self.data[payload_start..payload_end],
+        })
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<Chunk<'a>, RiffError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.pos == self.end {
+            return None;
+        }
+        let result = self.next_chunk();
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+impl std::iter::FusedIterator for Chunks<'_> {}
+
+/// Checks the RIFF header of `data` and iterates over its top-level chunks.
+///
+/// Bytes after the end of the RIFF container are ignored, as libwebp does.
+///
+/// ## Errors
+///
+/// Returns `Err` if the RIFF header is invalid. Errors in the chunks are
+/// reported by the iterator.
+pub fn chunks(data: &[u8]) -> Result<Chunks<'_>, RiffError> {
+    if data.len() < RIFF_HEADER_SIZE {
+        return Err(error(RiffErrorKind::Truncated, 0));
+    }
+    if &data[..4] != b"RIFF" {
+        return Err(error(RiffErrorKind::NotRiff, 0));
+    }
+    if &data[8..12] != b"WEBP" {
+        return Err(error(RiffErrorKind::NotWebP, 8));
+    }
+    let riff_size = read_u32(&data[4..]) as usize;
+    if riff_size < RIFF_HEADER_SIZE - 8 + CHUNK_HEADER_SIZE || riff_size & 1 != 0 {
+        return Err(error(RiffErrorKind::InvalidRiffSize, 4));
+    }
+    Ok(Chunks {
+        data,
+        base: 0,
+        pos: RIFF_HEADER_SIZE,
+        end: riff_size.saturating_add(8),
+        first: true,
+        done: false,
+    })
+}
+
+/// Summary of a WebP file, see [`parse`].
+///
+/// Offsets are those of the chunk headers. Only the first top-level
+/// occurrence of each chunk is recorded; image chunks nested in `ANMF`
+/// chunks are validated but not recorded.
+///
+/// [`parse`]: fn.parse.html
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RiffInfo {
+    /// Size of the RIFF container, including its header.
+    pub file_size: usize,
+    pub vp8x: Option<VP8XHeader>,
+    pub anim: Option<ANIMHeader>,
+    pub vp8_offset: Option<usize>,
+    pub vp8l_offset: Option<usize>,
+    pub vp8x_offset: Option<usize>,
+    pub alph_offset: Option<usize>,
+    pub anim_offset: Option<usize>,
+    pub iccp_offset: Option<usize>,
+    pub exif_offset: Option<usize>,
+    pub xmp_offset: Option<usize>,
+    pub anmf_offsets: Vec<usize>,
+    /// Number of top-level chunks, including unknown ones.
+    pub chunk_count: usize,
+}
+
+/// Walks the whole RIFF container of `data`, including the chunks nested
+/// in `ANMF` chunks.
+///
+/// ## Errors
+///
+/// Returns `Err` with the offset of the first malformed header or chunk.
+pub fn parse(data: &[u8]) -> Result<RiffInfo, RiffError> {
+    let chunks = chunks(data)?;
+    let mut info = RiffInfo {
+        file_size: chunks.end,
+        ..RiffInfo::default()
+    };
+    for chunk in chunks {
+        let chunk = chunk?;
+        info.chunk_count += 1;
+        let offset = Some(chunk.offset());
+        let slot = match &chunk.fourcc() {
+            b"VP8 " => &mut info.vp8_offset,
+            b"VP8L" => &mut info.vp8l_offset,
+            b"VP8X" => {
+                info.vp8x = chunk.vp8x();
+                &mut info.vp8x_offset
+            }
+            b"ALPH" => &mut info.alph_offset,
+            b"ANIM" => {
+                info.anim = chunk.anim();
+                &mut info.anim_offset
+            }
+            b"ICCP" => &mut info.iccp_offset,
+            b"EXIF" => &mut info.exif_offset,
+            b"XMP " => &mut info.xmp_offset,
+            b"ANMF" => {
+                for frame_chunk in chunk.frame_chunks().unwrap() {
+                    frame_chunk?;
+                }
+                info.anmf_offsets.push(chunk.offset());
+                continue;
+            }
+            _ => continue,
+        };
+        if slot.is_none() {
+            *slot = offset;
+        }
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::{chunk, riff, u24};
+
+    fn lena() -> Vec<u8> {
+        include_bytes!("lena.webp").to_vec()
+    }
+
+    // An animation with metadata, whose frames hold a dummy bitstream.
+    fn extended() -> Vec<u8> {
+        let vp8x = [&[0x3e, 0, 0, 0][..], &u24(99), &u24(49)].concat();
+        let anim = [0x11, 0x22, 0x33, 0x44, 3, 0];
+        let anmf = [
+            u24(5),
+            u24(10),
+            u24(19),
+            u24(9),
+            u24(100),
+            vec![0x03],
+            chunk(b"VP8L", &[0x2f, 0, 0, 0, 0]),
+        ]
+        .concat();
+        riff(&[
+            chunk(b"VP8X", &vp8x),
+            chunk(b"ICCP", b"icc"),
+            chunk(b"ANIM", &anim),
+            chunk(b"ANMF", &anmf),
+            chunk(b"ANMF", &anmf),
+            chunk(b"EXIF", b"exif"),
+            chunk(b"XMP ", b"<xmp/>"),
+            chunk(b"UNKN", b"?"),
+        ])
+    }
+
+    #[test]
+    fn test_simple() {
+        let data = lena();
+        let chunks = chunks(&data)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].fourcc(), FOURCC_VP8);
+        assert_eq!(chunks[0].payload_offset(), 20);
+        assert_eq!(chunks[0].size(), data.len() - 20);
+
+        let info = parse(&data).unwrap();
+        assert_eq!(info.file_size, data.len());
+        assert_eq!(info.vp8_offset, Some(12));
+        assert_eq!(info.vp8l_offset, None);
+        assert_eq!(info.vp8x, None);
+        assert_eq!(info.chunk_count, 1);
+    }
+
+    #[test]
+    fn test_extended() {
+        let data = extended();
+        let info = parse(&data).unwrap();
+        assert_eq!(
+            info.vp8x,
+            Some(VP8XHeader {
+                flags: 0x3e,
+                canvas_width: 100,
+                canvas_height: 50,
+            })
+        );
+        let vp8x = info.vp8x.unwrap();
+        assert!(vp8x.has_animation() && vp8x.has_iccp() && vp8x.has_exif());
+        assert!(vp8x.has_xmp() && vp8x.has_alpha());
+        assert_eq!(
+            info.anim,
+            Some(ANIMHeader {
+                bgcolor: 0x4433_2211,
+                loop_count: 3,
+            })
+        );
+        assert_eq!(info.vp8x_offset, Some(12));
+        assert_eq!(info.iccp_offset, Some(30));
+        assert_eq!(info.anim_offset, Some(42));
+        assert_eq!(info.anmf_offsets, vec![56, 94]);
+        assert_eq!(info.exif_offset, Some(132));
+        assert_eq!(info.xmp_offset, Some(144));
+        assert_eq!(info.vp8_offset, None);
+        assert_eq!(info.alph_offset, None);
+        assert_eq!(info.chunk_count, 8);
+
+        let anmf = chunks(&data).unwrap().nth(3).unwrap().unwrap();
+        assert_eq!(
+            anmf.anmf(),
+            Some(ANMFHeader {
+                x_offset: 10,
+                y_offset: 20,
+                width: 20,
+                height: 10,
+                duration: 100,
+                dispose_to_background: true,
+                blend: false,
+            })
+        );
+        assert_eq!(anmf.vp8x(), None);
+        let frame = anmf.frame_chunks().unwrap().collect::<Vec<_>>();
+        assert_eq!(frame.len(), 1);
+        let vp8l = frame[0].unwrap();
+        assert_eq!(vp8l.fourcc(), FOURCC_VP8L);
+        assert_eq!(vp8l.offset(), 56 + 8 + 16);
+        assert_eq!(&data[vp8l.payload_offset()..][..5], vp8l.payload());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let kind = |data: &[u8]| chunks(data).unwrap_err().kind;
+        assert_eq!(kind(b"RIFF"), RiffErrorKind::Truncated);
+        assert_eq!(kind(b"RIFX\x0c\0\0\0WEBP"), RiffErrorKind::NotRiff);
+        assert_eq!(kind(b"RIFF\x0c\0\0\0WAVE"), RiffErrorKind::NotWebP);
+        assert_eq!(
+            chunks(b"RIFF\x0b\0\0\0WEBP").unwrap_err(),
+            RiffError {
+                kind: RiffErrorKind::InvalidRiffSize,
+                offset: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_chunks() {
+        let first_error = |data: &[u8]| parse(data).unwrap_err();
+
+        let mut data = lena();
+        data.truncate(data.len() - 1);
+        assert_eq!(
+            first_error(&data),
+            RiffError {
+                kind: RiffErrorKind::Truncated,
+                offset: 12,
+            }
+        );
+
+        let mut data = riff(&[chunk(b"VP8L", &[0x2f, 0, 0, 0, 0])]);
+        data[16] = 7;
+        assert_eq!(
+            first_error(&data).kind,
+            RiffErrorKind::ChunkTooLarge(FOURCC_VP8L)
+        );
+        // Only possible in `ANMF` chunks, since the RIFF size must be even.
+        let vp8x = [&[0x02, 0, 0, 0][..], &u24(0), &u24(0)].concat();
+        let anmf = [&[0; 16][..], b"VP8L\x05\0\0\0", &[0x2f, 0, 0, 0, 0]].concat();
+        let data = riff(&[chunk(b"VP8X", &vp8x), chunk(b"ANMF", &anmf)]);
+        assert_eq!(
+            first_error(&data),
+            RiffError {
+                kind: RiffErrorKind::MissingPadding(FOURCC_VP8L),
+                offset: 30 + 8 + 16,
+            }
+        );
+
+        let data = riff(&[chunk(b"ICCP", b"icc")]);
+        assert_eq!(
+            first_error(&data).kind,
+            RiffErrorKind::UnexpectedFirstChunk(FOURCC_ICCP)
+        );
+
+        let data = riff(&[chunk(b"VP8X", &[0; 9])]);
+        assert_eq!(
+            first_error(&data).kind,
+            RiffErrorKind::ChunkTooSmall(FOURCC_VP8X)
+        );
+
+        // A broken chunk nested in the second frame.
+        let mut data = extended();
+        data[94 + 8 + 16 + 4] = 7;
+        assert_eq!(
+            first_error(&data),
+            RiffError {
+                kind: RiffErrorKind::ChunkTooLarge(FOURCC_VP8L),
+                offset: 94 + 8 + 16,
+            }
+        );
+
+        // Iteration stops after an error.
+        let mut iter = chunks(&data[..60]).unwrap();
+        assert!(iter.by_ref().take(3).all(|chunk| chunk.is_ok()));
+        assert_eq!(
+            iter.next().unwrap().unwrap_err().kind,
+            RiffErrorKind::Truncated
+        );
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let mut data = lena();
+        data.extend_from_slice(b"trailing");
+        assert_eq!(parse(&data).unwrap().file_size, data.len() - 8);
+    }
+}
@@ -0,0 +1,56 @@
+//! RIFF fixtures shared by the unit tests.
+
+/// Builds a chunk, with padding for odd-sized payloads.
+pub fn chunk(fourcc: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut data = fourcc.to_vec();
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        data.push(0);
+    }
+    data
+}
+
+/// Wraps `chunks` in a RIFF WebP header.
+pub fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut data = b"RIFF".to_vec();
+    data.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    data.extend_from_slice(b"WEBP");
+    data.extend_from_slice(&body);
+    data
+}
+
+pub fn u24(value: u32) -> Vec<u8> {
+    value.to_le_bytes()[..3].to_vec()
+}
+
+/// Three 4x4 lossless frames at x = 0, 2, 4 on an 8x4 canvas, lasting 100,
+/// 200 and 300 ms, with background color `0xff0000ff` and loop count 2. The
+/// second frame is translucent green and is not blended.
+#[cfg(any(feature = "mux", feature = "demux"))]
+pub fn animation() -> Vec<u8> {
+    let vp8x = [&[0x12, 0, 0, 0][..], &u24(7), &u24(3)].concat();
+    let anim = [0xff, 0x00, 0x00, 0xff, 2, 0];
+    let mut chunks = vec![chunk(b"VP8X", &vp8x), chunk(b"ANIM", &anim)];
+    for (i, color) in [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 255]]
+        .iter()
+        .enumerate()
+    {
+        let bitstream =
+            crate::encode::WebPEncodeLosslessRGBA(&color.repeat(4 * 4), 4, 4, 16).unwrap();
+        let flags = if i == 1 { 0x02 } else { 0x00 };
+        let anmf = [
+            u24(i as u32),
+            u24(0),
+            u24(3),
+            u24(3),
+            u24(100 * (i as u32 + 1)),
+            vec![flags],
+            bitstream[12..].to_vec(),
+        ]
+        .concat();
+        chunks.push(chunk(b"ANMF", &anmf));
+    }
+    riff(&chunks)
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::decode::WebPGetDecoderVersion;
+use crate::encode::WebPGetEncoderVersion;
+
+/// A libwebp version number.
+///
+/// Versions are ordered by `major`, `minor` and then `revision`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::Version;
+///
+/// let version = Version::from_packed(0x010203);
+/// assert_eq!(version, Version::new(1, 2, 3));
+/// assert_eq!(version.to_string(), "1.2.3");
+/// assert!(version > Version::new(0, 6, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+}
+
+impl Version {
+    pub const fn new(major: u8, minor: u8, revision: u8) -> Self {
+        Self {
+            major,
+            minor,
+            revision,
+        }
+    }
+
+    /// Unpacks a version as returned by [`WebPGetDecoderVersion`] and
+    /// [`WebPGetEncoderVersion`], in the form `0xMMmmrr`.
+    ///
+    /// [`WebPGetDecoderVersion`]: fn.WebPGetDecoderVersion.html
+    /// [`WebPGetEncoderVersion`]: fn.WebPGetEncoderVersion.html
+    pub fn from_packed(packed: u32) -> Self {
+        Self::new((packed >> 16) as u8, (packed >> 8) as u8, packed as u8)
+    }
+
+    /// Packs the version in the form `0xMMmmrr`.
+    pub fn into_packed(self) -> u32 {
+        u32::from(self.major) << 16 | u32::from(self.minor) << 8 | u32::from(self.revision)
+    }
+
+    /// Returns the version of the linked decoder library.
+    pub fn decoder() -> Self {
+        Self::from_packed(WebPGetDecoderVersion())
+    }
+
+    /// Returns the version of the linked encoder library.
+    pub fn encoder() -> Self {
+        Self::from_packed(WebPGetEncoderVersion())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.revision)
+    }
+}
+
+/// Optional functions available in a libwebp version.
+///
+/// The corresponding bindings are only compiled in with the matching
+/// `0_5`, `0_6` or `1_1` feature. Use [`capabilities`] to query the library
+/// linked at runtime.
+///
+/// [`capabilities`]: fn.capabilities.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    version: Version,
+}
+
+impl Capabilities {
+    /// Returns the capabilities of the given libwebp version.
+    pub fn for_version(version: Version) -> Self {
+        Self { version }
+    }
+
+    /// The version these capabilities are derived from.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// `WebPFree` (`0_5`).
+    pub fn free(&self) -> bool {
+        self.version >= Version::new(0, 5, 0)
+    }
+
+    /// `WebPMalloc` (`1_1`).
+    pub fn malloc(&self) -> bool {
+        self.version >= Version::new(1, 1, 0)
+    }
+
+    /// `WebPConfigLosslessPreset` (`0_5`).
+    pub fn lossless_preset(&self) -> bool {
+        self.version >= Version::new(0, 5, 0)
+    }
+
+    /// `exact` and `near_lossless` in `WebPConfig` (`0_5`).
+    pub fn exact_and_near_lossless(&self) -> bool {
+        self.version >= Version::new(0, 5, 0)
+    }
+
+    /// `WebPPictureSmartARGBToYUVA` (`0_5`).
+    pub fn smart_argb_to_yuva(&self) -> bool {
+        self.version >= Version::new(0, 5, 0)
+    }
+
+    /// `WebPPictureSharpARGBToYUVA` and `use_sharp_yuv` in `WebPConfig`
+    /// (`0_6`).
+    pub fn sharp_yuv(&self) -> bool {
+        self.version >= Version::new(0, 6, 0)
+    }
+
+    /// `use_delta_palette` in `WebPConfig` (`0_6`).
+    pub fn delta_palette(&self) -> bool {
+        self.version >= Version::new(0, 6, 0)
+    }
+
+    /// `WebPPlaneDistortion` (`0_6`).
+    pub fn plane_distortion(&self) -> bool {
+        self.version >= Version::new(0, 6, 0)
+    }
+}
+
+/// Queries which optional functions the linked libwebp supports.
+///
+/// The lower of the decoder and encoder versions is used, in case they come
+/// from different builds.
+///
+/// ## Examples
+///
+/// ```rust
+/// use libwebp::capabilities;
+///
+/// let caps = capabilities();
+/// if caps.sharp_yuv() {
+///     eprintln!("libwebp {} supports sharp YUV", caps.version());
+/// }
+/// ```
+pub fn capabilities() -> Capabilities {
+    Capabilities::for_version(Version::decoder().min(Version::encoder()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_packed() {
+        let version = Version::from_packed(0x00_06_01);
+        assert_eq!(version, Version::new(0, 6, 1));
+        assert_eq!(version.into_packed(), 0x00_06_01);
+        assert_eq!(version.to_string(), "0.6.1");
+    }
+
+    #[test]
+    fn test_version_ord() {
+        assert!(Version::new(0, 5, 2) < Version::new(0, 6, 0));
+        assert!(Version::new(0, 6, 9) < Version::new(1, 0, 0));
+        assert!(Version::new(1, 1, 0) < Version::new(1, 1, 1));
+    }
+
+    #[test]
+    fn test_linked_version() {
+        assert_eq!(Version::decoder().into_packed(), WebPGetDecoderVersion());
+        assert_eq!(Version::encoder().into_packed(), WebPGetEncoderVersion());
+        assert!(Version::decoder() >= Version::new(0, 4, 0));
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = Capabilities::for_version(Version::new(0, 5, 1));
+        assert!(caps.free());
+        assert!(caps.lossless_preset());
+        assert!(!caps.sharp_yuv());
+        assert!(!caps.malloc());
+
+        let caps = Capabilities::for_version(Version::new(1, 1, 0));
+        assert!(caps.sharp_yuv());
+        assert!(caps.malloc());
+
+        let caps = Capabilities::for_version(Version::new(0, 4, 4));
+        assert!(!caps.free());
+        assert!(!caps.exact_and_near_lossless());
+
+        assert_eq!(
+            capabilities().version(),
+            Version::decoder().min(Version::encoder())
+        );
+    }
+}